@@ -7,7 +7,8 @@ use std::{
 
 use ff::PrimeField;
 use nova_scotia::{
-    circom::reader::load_r1cs, create_public_params, create_recursive_circuit, FileLocation, F,
+    circom::reader::load_r1cs, create_public_params, create_recursive_circuit_from_source,
+    FileLocation, F,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::json;
@@ -27,7 +28,7 @@ fn bench(iteration_count: usize, per_iteration_count: usize) -> (Duration, Durat
     let root = current_dir().unwrap();
 
     let circuit_file = root.join("examples/bitcoin/circom/bitcoin_benchmark.r1cs");
-    let r1cs = load_r1cs::<G1, G2>(&FileLocation::PathBuf(circuit_file));
+    let r1cs = load_r1cs::<G1, G2>(&FileLocation::PathBuf(circuit_file)).unwrap();
     let witness_generator_file =
         root.join("examples/bitcoin/circom/bitcoin_benchmark_cpp/bitcoin_benchmark");
 
@@ -40,28 +41,20 @@ fn bench(iteration_count: usize, per_iteration_count: usize) -> (Duration, Durat
         F::<G1>::from_str_vartime(&btc_blocks.prevBlockHash[1]).unwrap(),
     ];
 
-    let mut private_inputs = Vec::new();
-
-    for i in 0..iteration_count {
-        let mut private_input = HashMap::new();
-        private_input.insert(
-            "blockHashes".to_string(),
-            json!(
-                btc_blocks.blockHashes
-                    [i * per_iteration_count..i * per_iteration_count + per_iteration_count]
+    // each step's private inputs are only built when the prover asks for them
+    let private_inputs = (0..iteration_count).map(|i| {
+        let blocks = i * per_iteration_count..i * per_iteration_count + per_iteration_count;
+        HashMap::from([
+            (
+                "blockHashes".to_string(),
+                json!(btc_blocks.blockHashes[blocks.clone()]),
             ),
-        );
-        private_input.insert(
-            "blockHeaders".to_string(),
-            json!(
-                btc_blocks.blockHeaders
-                    [i * per_iteration_count..i * per_iteration_count + per_iteration_count]
+            (
+                "blockHeaders".to_string(),
+                json!(btc_blocks.blockHeaders[blocks]),
             ),
-        );
-        private_inputs.push(private_input);
-    }
-
-    // println!("{:?} {:?}", start_public_input, private_inputs);
+        ])
+    });
 
     let pp = create_public_params::<G1, G2>(r1cs.clone()).unwrap();
 
@@ -85,7 +78,7 @@ fn bench(iteration_count: usize, per_iteration_count: usize) -> (Duration, Durat
 
     println!("Creating a RecursiveSNARK...");
     let start = Instant::now();
-    let recursive_snark = create_recursive_circuit(
+    let output = create_recursive_circuit_from_source(
         FileLocation::PathBuf(witness_generator_file),
         r1cs,
         private_inputs,
@@ -99,7 +92,9 @@ fn bench(iteration_count: usize, per_iteration_count: usize) -> (Duration, Durat
     // verify the recursive SNARK
     println!("Verifying a RecursiveSNARK...");
     let start = Instant::now();
-    let res = recursive_snark.verify(&pp, iteration_count, &start_public_input);
+    let res = output
+        .recursive_snark
+        .verify(&pp, iteration_count, &start_public_input);
     println!(
         "RecursiveSNARK::verify: {:?}, took {:?}",
         res,
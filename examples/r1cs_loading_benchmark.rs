@@ -0,0 +1,71 @@
+use std::{fs::File, io::BufReader, time::Instant};
+
+use ff::Field;
+use nova_scotia::{
+    circom::{
+        circuit::{IoLayout, R1CS},
+        file::from_reader,
+        reader::load_r1cs,
+        writer::save_r1cs,
+    },
+    FileLocation, F,
+};
+
+type G1 = nova_snark::provider::PallasEngine;
+type G2 = nova_snark::provider::VestaEngine;
+
+const NUM_CONSTRAINTS: usize = 1_000_000;
+
+// the chain of `v[i + 1] <== (v[i] + 3 v[i - 1] + 1) * v[i]` from step_circuit_benchmark.rs
+fn synthetic_r1cs() -> R1CS<F<G1>> {
+    let num_inputs = 3;
+    let num_variables = num_inputs + NUM_CONSTRAINTS + 1;
+    let constraints = (0..NUM_CONSTRAINTS)
+        .map(|i| {
+            let v = num_inputs + i;
+            (
+                vec![
+                    (v, F::<G1>::ONE),
+                    (v - 1, F::<G1>::from(3)),
+                    (0, F::<G1>::ONE),
+                ],
+                vec![(v, F::<G1>::ONE)],
+                vec![(v + 1, F::<G1>::ONE)],
+            )
+        })
+        .collect();
+    R1CS {
+        num_inputs,
+        num_outputs: num_inputs / 2,
+        num_aux: num_variables - num_inputs,
+        num_variables,
+        constraints,
+        io: IoLayout::symmetric(num_inputs),
+    }
+}
+
+fn main() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("synthetic.r1cs");
+    let r1cs = synthetic_r1cs();
+    save_r1cs(&r1cs, &path).unwrap();
+    println!(
+        "Synthetic R1CS file with {} constraints, {} MiB",
+        NUM_CONSTRAINTS,
+        std::fs::metadata(&path).unwrap().len() >> 20
+    );
+
+    let start = Instant::now();
+    let file = from_reader::<_, G1, G2>(BufReader::new(File::open(&path).unwrap())).unwrap();
+    println!("Sequential parsing through a reader: {:?}", start.elapsed());
+
+    let start = Instant::now();
+    let loaded = load_r1cs::<G1, G2>(&FileLocation::PathBuf(path)).unwrap();
+    println!(
+        "Memory-mapped parsing on {} threads: {:?}",
+        rayon::current_num_threads(),
+        start.elapsed()
+    );
+
+    assert!(file.constraints == loaded.constraints && loaded.constraints == r1cs.constraints);
+}
@@ -0,0 +1,115 @@
+use std::{sync::Arc, time::Duration, time::Instant};
+
+use ff::Field;
+use nova_scotia::{
+    circom::circuit::{CircomCircuit, IoLayout, R1CS},
+    create_public_params, C1, F,
+};
+use nova_snark::nova::{PublicParams, RecursiveSNARK};
+
+type G1 = nova_snark::provider::PallasEngine;
+type G2 = nova_snark::provider::VestaEngine;
+
+const NUM_CONSTRAINTS: usize = 150_000;
+const NUM_STEPS: usize = 10;
+
+// wire 1 is step_out[0], wire 2 step_in[0], and the aux wires hold a chain of
+// `v[i + 1] <== (v[i] + 3 v[i - 1] + 1) * v[i]` starting from step_in[0], about the size of the
+// bitcoin circuit; its last value is copied to step_out[0]
+fn synthetic_r1cs() -> R1CS<F<G1>> {
+    let num_inputs = 3;
+    let num_variables = num_inputs + NUM_CONSTRAINTS + 1;
+    let mut constraints = (0..NUM_CONSTRAINTS)
+        .map(|i| {
+            let v = num_inputs + i;
+            (
+                vec![
+                    (v, F::<G1>::ONE),
+                    (v - 1, F::<G1>::from(3)),
+                    (0, F::<G1>::ONE),
+                ],
+                vec![(v, F::<G1>::ONE)],
+                vec![(v + 1, F::<G1>::ONE)],
+            )
+        })
+        .collect::<Vec<_>>();
+    constraints.push((
+        vec![(num_variables - 1, F::<G1>::ONE)],
+        vec![(0, F::<G1>::ONE)],
+        vec![(1, F::<G1>::ONE)],
+    ));
+    R1CS {
+        num_inputs,
+        num_outputs: num_inputs / 2,
+        num_aux: num_variables - num_inputs,
+        num_variables,
+        constraints,
+        io: IoLayout::symmetric(num_inputs),
+    }
+}
+
+// a witness satisfying `synthetic_r1cs` for `step_in[0] = z`
+fn synthetic_witness(r1cs: &R1CS<F<G1>>, z: F<G1>) -> Vec<F<G1>> {
+    let mut witness = vec![F::<G1>::ONE, F::<G1>::ZERO, z, F::<G1>::ONE];
+    for v in 3..r1cs.num_variables - 1 {
+        let next = (witness[v] + witness[v - 1] * F::<G1>::from(3) + F::<G1>::ONE) * witness[v];
+        witness.push(next);
+    }
+    witness[1] = witness[r1cs.num_variables - 1];
+    witness
+}
+
+// fold `NUM_STEPS` steps, building each step's circuit with `step_circuit`, and return the
+// average time per step
+fn prove_steps(
+    pp: &PublicParams<G1, G2, C1<G1>>,
+    r1cs: &Arc<R1CS<F<G1>>>,
+    step_circuit: impl Fn(&Arc<R1CS<F<G1>>>, Vec<F<G1>>) -> C1<G1>,
+) -> Duration {
+    let z0 = vec![F::<G1>::from(2)];
+    let mut z_i = z0.clone();
+    let mut recursive_snark: Option<RecursiveSNARK<G1, G2, C1<G1>>> = None;
+    let start = Instant::now();
+    for _ in 0..NUM_STEPS {
+        let circuit = step_circuit(r1cs, synthetic_witness(r1cs, z_i[0]));
+        z_i = circuit.get_public_outputs();
+        let snark = match recursive_snark.as_mut() {
+            Some(snark) => snark,
+            None => recursive_snark.insert(RecursiveSNARK::new(pp, &circuit, &z0).unwrap()),
+        };
+        snark.prove_step(pp, &circuit).unwrap();
+    }
+    let per_step = start.elapsed() / NUM_STEPS as u32;
+
+    let z_n = recursive_snark.unwrap().verify(pp, NUM_STEPS, &z0).unwrap();
+    assert_eq!(z_n, z_i);
+    per_step
+}
+
+fn main() {
+    let r1cs = synthetic_r1cs();
+    println!(
+        "Synthetic step circuit with {} constraints, {} steps",
+        r1cs.constraints.len(),
+        NUM_STEPS
+    );
+
+    let start = Instant::now();
+    let pp = create_public_params::<G1, G2>(r1cs.clone()).unwrap();
+    println!("Creating public parameters took {:?}", start.elapsed());
+    let r1cs = Arc::new(r1cs);
+
+    // every step used to own a copy of the R1CS
+    let cloned = prove_steps(&pp, &r1cs, |r1cs, witness| CircomCircuit {
+        r1cs: Arc::new(R1CS::clone(r1cs)),
+        witness: Some(witness),
+    });
+    println!("prove_step, copying the R1CS per step: {:?}", cloned);
+
+    let shared = prove_steps(&pp, &r1cs, |r1cs, witness| CircomCircuit {
+        r1cs: Arc::clone(r1cs),
+        witness: Some(witness),
+    });
+    println!("prove_step, sharing the R1CS: {:?}", shared);
+    println!("Saved per step: {:?}", cloned.saturating_sub(shared));
+}
@@ -1,10 +1,11 @@
 use std::{collections::HashMap, env::current_dir, time::Instant};
 
 use nova_scotia::{
-    circom::reader::load_r1cs, continue_recursive_circuit, create_public_params,
-    create_recursive_circuit, FileLocation, F, S,
+    circom::reader::load_r1cs, compress, compression::Spartan, continue_recursive_circuit,
+    create_public_params, create_recursive_circuit, setup_compression, verify_compressed,
+    FileLocation, F,
 };
-use nova_snark::{provider, nova::{CompressedSNARK}};
+use nova_snark::provider;
 use serde_json::json;
 
 fn run_test(circuit_filepath: String, witness_gen_filepath: String) {
@@ -20,7 +21,7 @@ fn run_test(circuit_filepath: String, witness_gen_filepath: String) {
     let root = current_dir().unwrap();
 
     let circuit_file = root.join(circuit_filepath);
-    let r1cs = load_r1cs::<G1, G2>(&FileLocation::PathBuf(circuit_file));
+    let r1cs = load_r1cs::<G1, G2>(&FileLocation::PathBuf(circuit_file)).unwrap();
     let witness_generator_file = root.join(witness_gen_filepath);
 
     let mut private_inputs = Vec::new();
@@ -54,7 +55,7 @@ fn run_test(circuit_filepath: String, witness_gen_filepath: String) {
 
     println!("Creating a RecursiveSNARK...");
     let start = Instant::now();
-    let mut recursive_snark = create_recursive_circuit(
+    let output = create_recursive_circuit(
         FileLocation::PathBuf(witness_generator_file.clone()),
         r1cs.clone(),
         private_inputs,
@@ -63,6 +64,7 @@ fn run_test(circuit_filepath: String, witness_gen_filepath: String) {
     )
     .unwrap();
     println!("RecursiveSNARK creation took {:?}", start.elapsed());
+    let mut recursive_snark = output.recursive_snark;
 
     // TODO: empty?
     // verify the recursive SNARK
@@ -77,6 +79,7 @@ fn run_test(circuit_filepath: String, witness_gen_filepath: String) {
     assert!(res.is_ok());
 
     let z_last = res.unwrap();
+    assert_eq!(z_last, output.zn);
 
     assert_eq!(z_last[0], F::<G1>::from(20));
     assert_eq!(z_last[1], F::<G1>::from(70));
@@ -84,8 +87,8 @@ fn run_test(circuit_filepath: String, witness_gen_filepath: String) {
     // produce a compressed SNARK
     println!("Generating a CompressedSNARK using Spartan with IPA-PC...");
     let start = Instant::now();
-    let (pk, vk) = CompressedSNARK::<_, _, _, S<G1>, S<G2>>::setup(&pp).unwrap();
-    let res = CompressedSNARK::<_, _, _, S<G1>, S<G2>>::prove(&pp, &pk, &recursive_snark);
+    let (pk, vk) = setup_compression::<G1, G2, Spartan>(&pp).unwrap();
+    let res = compress::<G1, G2, Spartan>(&pp, &pk, &recursive_snark);
     println!(
         "CompressedSNARK::prove: {:?}, took {:?}",
         res.is_ok(),
@@ -97,8 +100,9 @@ fn run_test(circuit_filepath: String, witness_gen_filepath: String) {
     // verify the compressed SNARK
     println!("Verifying a CompressedSNARK...");
     let start = Instant::now();
-    let res = compressed_snark.verify(
+    let res = verify_compressed::<G1, G2, Spartan>(
         &vk,
+        &compressed_snark,
         iteration_count,
         &start_public_input,
     );
@@ -131,16 +135,22 @@ fn run_test(circuit_filepath: String, witness_gen_filepath: String) {
         start_public_input.to_vec(),
         &pp,
     );
-    assert!(res.is_ok());
     println!(
         "Adding 2 steps to our RecursiveSNARK took {:?}",
         start.elapsed()
     );
+    let (num_steps, z_last) = res.unwrap();
+    assert_eq!(num_steps, iteration_count + iteration_count_continue);
+    assert_eq!(z_last[1], F::<G1>::from(115));
 
     // verify the recursive SNARK with the added steps
     println!("Verifying a RecursiveSNARK...");
     let start = Instant::now();
-    let res = recursive_snark.verify(&pp, iteration_count + iteration_count_continue, &start_public_input);
+    let res = recursive_snark.verify(
+        &pp,
+        iteration_count + iteration_count_continue,
+        &start_public_input,
+    );
     println!(
         "RecursiveSNARK::verify: {:?}, took {:?}",
         res,
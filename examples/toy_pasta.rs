@@ -1,10 +1,8 @@
 use std::{collections::HashMap, env::current_dir, time::Instant};
 
 use nova_scotia::{
-    circom::reader::load_r1cs, create_public_params, create_recursive_circuit, FileLocation, F, S,
-};
-use nova_snark::{
-    nova::{CompressedSNARK},
+    circom::reader::load_r1cs, compress, compression::Spartan, create_public_params,
+    create_recursive_circuit, setup_compression, verify_compressed, FileLocation, F,
 };
 use serde_json::json;
 
@@ -21,7 +19,7 @@ fn run_test(circuit_filepath: String, witness_gen_filepath: String) {
     let root = current_dir().unwrap();
 
     let circuit_file = root.join(circuit_filepath);
-    let r1cs = load_r1cs::<G1, G2>(&FileLocation::PathBuf(circuit_file));
+    let r1cs = load_r1cs::<G1, G2>(&FileLocation::PathBuf(circuit_file)).unwrap();
     let witness_generator_file = root.join(witness_gen_filepath);
 
     let mut private_inputs = Vec::new();
@@ -55,7 +53,7 @@ fn run_test(circuit_filepath: String, witness_gen_filepath: String) {
 
     println!("Creating a RecursiveSNARK...");
     let start = Instant::now();
-    let recursive_snark = create_recursive_circuit(
+    let output = create_recursive_circuit(
         FileLocation::PathBuf(witness_generator_file),
         r1cs,
         private_inputs,
@@ -64,6 +62,11 @@ fn run_test(circuit_filepath: String, witness_gen_filepath: String) {
     )
     .unwrap();
     println!("RecursiveSNARK creation took {:?}", start.elapsed());
+    println!(
+        "Public outputs after {} steps: {:?}",
+        output.num_steps, output.zn
+    );
+    let recursive_snark = output.recursive_snark;
 
     // TODO: empty?
     // verify the recursive SNARK
@@ -81,8 +84,8 @@ fn run_test(circuit_filepath: String, witness_gen_filepath: String) {
     println!("Generating a CompressedSNARK using Spartan with IPA-PC...");
     let start = Instant::now();
 
-    let (pk, vk) = CompressedSNARK::<_, _, _, S<G1>, S<G2>>::setup(&pp).unwrap();
-    let res = CompressedSNARK::<_, _, _, S<G1>, S<G2>>::prove(&pp, &pk, &recursive_snark);
+    let (pk, vk) = setup_compression::<G1, G2, Spartan>(&pp).unwrap();
+    let res = compress::<G1, G2, Spartan>(&pp, &pk, &recursive_snark);
     println!(
         "CompressedSNARK::prove: {:?}, took {:?}",
         res.is_ok(),
@@ -94,8 +97,9 @@ fn run_test(circuit_filepath: String, witness_gen_filepath: String) {
     // verify the compressed SNARK
     println!("Verifying a CompressedSNARK...");
     let start = Instant::now();
-    let res = compressed_snark.verify(
+    let res = verify_compressed::<G1, G2, Spartan>(
         &vk,
+        &compressed_snark,
         iteration_count,
         &start_public_input,
     );
@@ -0,0 +1,688 @@
+// Command-line front end for proving and verifying Circom step circuits with Nova.
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use byteorder::{LittleEndian, ReadBytesExt};
+use clap::{Parser, Subcommand, ValueEnum};
+use ff::PrimeField;
+use nova_scotia::{
+    bundle::{self, read_bundle_header, ProofBundle, ProofKind},
+    checkpoint::Checkpoint,
+    circom::{
+        circuit::R1CS,
+        file::header_from_reader,
+        prime::SupportedEngine,
+        reader::{load_r1cs, load_r1cs_with_symbols},
+    },
+    compress,
+    compression::{
+        load_prover_key, save_prover_key, save_verifier_key, CompressionBackend,
+        PreprocessingSpartan, Spartan,
+    },
+    convert::{parse, to_decimal, to_hex},
+    create_public_params_for, create_recursive_circuit_with_checkpoints_from_source,
+    inputs::JsonlInputs,
+    params::{load_public_params, read_params_header, save_public_params, Compression},
+    resume_recursive_circuit_from_source, setup_compression,
+    verifier::Verifier,
+    FileLocation, NovaScotiaError, EE, F,
+};
+use nova_snark::{
+    provider::{Bn256EngineIPA, GrumpkinEngine, PallasEngine, VestaEngine},
+    traits::{evaluation::EvaluationEngineTrait, Engine},
+};
+use num_bigint::BigUint;
+
+// no field Nova supports is wider than this, so a larger field size in a file is garbage
+const MAX_FIELD_SIZE: u32 = 64;
+
+#[derive(Parser)]
+#[command(
+    name = "nova-scotia",
+    version,
+    about = "Prove and verify Circom step circuits with Nova"
+)]
+struct Cli {
+    /// cycle of curves the circuit was compiled for
+    #[arg(long, value_enum, default_value_t = Curve::Pasta, global = true)]
+    curve: Curve,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Curve {
+    /// Pallas/Vesta, circuits compiled with `circom --prime vesta`
+    Pasta,
+    /// BN254/Grumpkin, circuits compiled with `circom --prime bn128`
+    Bn254,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Backend {
+    /// Spartan over IPA-PC
+    Spartan,
+    /// preprocessing Spartan over IPA-PC, faster to verify but slower to prove
+    PreprocessingSpartan,
+}
+
+#[derive(clap::Args)]
+struct CircuitArgs {
+    /// R1CS file produced by `circom --r1cs`
+    #[arg(long)]
+    r1cs: PathBuf,
+
+    /// symbol file produced by `circom --sym`, to locate `step_in` and `step_out` by name
+    #[arg(long)]
+    sym: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create public parameters and compression keys for a circuit
+    Setup {
+        #[command(flatten)]
+        circuit: CircuitArgs,
+
+        /// where to write the public parameters
+        #[arg(long, short)]
+        output: PathBuf,
+
+        /// gzip the public parameters
+        #[arg(long)]
+        compress: bool,
+
+        /// compression backend to create the keys for
+        #[arg(long, value_enum, default_value_t = Backend::Spartan)]
+        backend: Backend,
+
+        /// where to write the prover key, `<output>.pk` by default
+        #[arg(long)]
+        prover_key: Option<PathBuf>,
+
+        /// where to write the verifier key, `<output>.vk` by default
+        #[arg(long)]
+        verifier_key: Option<PathBuf>,
+    },
+    /// Fold one step per line of a JSONL file of private inputs into a RecursiveSNARK
+    Prove {
+        #[command(flatten)]
+        circuit: CircuitArgs,
+
+        /// public parameters written by `setup`
+        #[arg(long)]
+        params: PathBuf,
+
+        /// circom witness generator, either a C++ binary or a `.wasm` file
+        #[arg(long)]
+        witness_generator: PathBuf,
+
+        /// JSONL file with one JSON object of private inputs per step
+        #[arg(long)]
+        inputs: PathBuf,
+
+        /// initial `step_in` values, decimal or 0x-prefixed hex
+        #[arg(long = "step-in", required = true, num_args = 1.., value_delimiter = ',')]
+        step_in: Vec<String>,
+
+        /// where to write the proof bundle
+        #[arg(long, short)]
+        output: PathBuf,
+
+        /// checkpoint file, `<output>.checkpoint` by default
+        #[arg(long)]
+        checkpoint: Option<PathBuf>,
+
+        /// also save a checkpoint every N steps, 0 only saves it at the end
+        #[arg(long, default_value_t = 0)]
+        checkpoint_interval: usize,
+
+        /// continue from the checkpoint instead of starting over
+        #[arg(long)]
+        resume: bool,
+    },
+    /// Compress the RecursiveSNARK in a proof bundle with the keys written by `setup`
+    Compress {
+        #[command(flatten)]
+        circuit: CircuitArgs,
+
+        /// public parameters written by `setup`
+        #[arg(long)]
+        params: PathBuf,
+
+        /// proof bundle written by `prove`
+        #[arg(long)]
+        proof: PathBuf,
+
+        /// where to write the compressed proof bundle
+        #[arg(long, short)]
+        output: PathBuf,
+
+        /// compression backend the prover key was created for
+        #[arg(long, value_enum, default_value_t = Backend::Spartan)]
+        backend: Backend,
+
+        /// prover key written by `setup`
+        #[arg(long)]
+        prover_key: PathBuf,
+    },
+    /// Verify a proof bundle written by `prove` or `compress`
+    Verify {
+        /// proof bundle to verify
+        #[arg(long)]
+        proof: PathBuf,
+
+        /// R1CS and symbol files, needed to verify a RecursiveSNARK
+        #[arg(long)]
+        r1cs: Option<PathBuf>,
+        #[arg(long)]
+        sym: Option<PathBuf>,
+
+        /// public parameters, needed to verify a RecursiveSNARK
+        #[arg(long)]
+        params: Option<PathBuf>,
+
+        /// verifier key written by `setup`, needed to verify a CompressedSNARK
+        #[arg(long)]
+        verifier_key: Option<PathBuf>,
+    },
+    /// Describe an R1CS, witness, public parameters or proof file
+    Inspect { file: PathBuf },
+}
+
+fn load_circuit<G1, G2>(r1cs: &Path, sym: Option<&Path>) -> Result<R1CS<F<G1>>, NovaScotiaError>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+{
+    let r1cs = FileLocation::PathBuf(r1cs.to_path_buf());
+    match sym {
+        Some(sym) => load_r1cs_with_symbols::<G1, G2>(&r1cs, sym),
+        None => load_r1cs::<G1, G2>(&r1cs),
+    }
+}
+
+fn format_fields<Fr: PrimeField>(values: &[Fr]) -> String {
+    let values = values.iter().map(to_decimal).collect::<Vec<_>>();
+    format!("[{}]", values.join(", "))
+}
+
+// `path` with `suffix` appended to its file name
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+fn setup<G1, G2, B>(
+    circuit: CircuitArgs,
+    output: &Path,
+    compression: Compression,
+    prover_key: &Path,
+    verifier_key: &Path,
+) -> Result<(), NovaScotiaError>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+    B: CompressionBackend<G1, G2>,
+{
+    let r1cs = load_circuit::<G1, G2>(&circuit.r1cs, circuit.sym.as_deref())?;
+    let pp = create_public_params_for::<G1, G2, B>(r1cs.clone())?;
+    save_public_params(&pp, &r1cs, output, compression)?;
+    let (pk, vk) = setup_compression::<G1, G2, B>(&pp)?;
+    save_prover_key::<G1, G2, B>(&pk, prover_key)?;
+    save_verifier_key::<G1, G2, B>(&vk, verifier_key)?;
+    let (primary, secondary) = pp.num_constraints();
+    println!(
+        "wrote public parameters for {} + {} constraints per step to {}, and {} keys to {} and {}",
+        primary,
+        secondary,
+        output.display(),
+        B::NAME,
+        prover_key.display(),
+        verifier_key.display()
+    );
+    Ok(())
+}
+
+fn compress_proof<G1, G2, B>(
+    circuit: CircuitArgs,
+    params: &Path,
+    proof: &Path,
+    output: &Path,
+    prover_key: &Path,
+) -> Result<(), NovaScotiaError>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+    B: CompressionBackend<G1, G2>,
+{
+    let r1cs = load_circuit::<G1, G2>(&circuit.r1cs, circuit.sym.as_deref())?;
+    let pp = load_public_params::<G1, G2>(params, &r1cs)?;
+    let proof = ProofBundle::<G1, G2>::load(proof)?;
+    proof.check_r1cs(&r1cs)?;
+    proof.check_params(&pp)?;
+    let recursive_snark = proof.recursive_snark()?;
+
+    let pk = load_prover_key::<G1, G2, B>(prover_key)?;
+    let snark = compress::<G1, G2, B>(&pp, &pk, &recursive_snark)?;
+    ProofBundle::compressed::<B>(&r1cs, &pp, &recursive_snark, &proof.header.z0, &snark)?
+        .save(output)?;
+    println!("wrote {} compressed proof to {}", B::NAME, output.display());
+    Ok(())
+}
+
+fn run<G1, G2>(command: Command) -> Result<(), NovaScotiaError>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+    EE<G1>: EvaluationEngineTrait<G1>,
+    EE<G2>: EvaluationEngineTrait<G2>,
+{
+    match command {
+        Command::Setup {
+            circuit,
+            output,
+            compress,
+            backend,
+            prover_key,
+            verifier_key,
+        } => {
+            let compression = if compress {
+                Compression::Gzip
+            } else {
+                Compression::None
+            };
+            let prover_key = prover_key.unwrap_or_else(|| with_suffix(&output, ".pk"));
+            let verifier_key = verifier_key.unwrap_or_else(|| with_suffix(&output, ".vk"));
+            match backend {
+                Backend::Spartan => setup::<G1, G2, Spartan>(
+                    circuit,
+                    &output,
+                    compression,
+                    &prover_key,
+                    &verifier_key,
+                )?,
+                Backend::PreprocessingSpartan => setup::<G1, G2, PreprocessingSpartan>(
+                    circuit,
+                    &output,
+                    compression,
+                    &prover_key,
+                    &verifier_key,
+                )?,
+            }
+        }
+        Command::Prove {
+            circuit,
+            params,
+            witness_generator,
+            inputs,
+            step_in,
+            output: output_file,
+            checkpoint,
+            checkpoint_interval,
+            resume,
+        } => {
+            let checkpoint_file =
+                checkpoint.unwrap_or_else(|| with_suffix(&output_file, ".checkpoint"));
+            let r1cs = load_circuit::<G1, G2>(&circuit.r1cs, circuit.sym.as_deref())?;
+            let pp = load_public_params::<G1, G2>(&params, &r1cs)?;
+            let mut private_inputs = JsonlInputs::open(&inputs)?;
+            let start_public_input = step_in
+                .iter()
+                .map(|value| parse::<F<G1>>(value))
+                .collect::<Result<Vec<_>, _>>()?;
+            let witness_generator = FileLocation::PathBuf(witness_generator);
+
+            let output = if resume {
+                let checkpoint = Checkpoint::<G1, G2>::load(&checkpoint_file)?;
+                if checkpoint.z0 != start_public_input {
+                    return Err(NovaScotiaError::InvalidInput(format!(
+                        "{} started from step_in {}",
+                        checkpoint_file.display(),
+                        format_fields(&checkpoint.z0)
+                    )));
+                }
+                private_inputs.skip(checkpoint.num_steps)?;
+                resume_recursive_circuit_from_source(
+                    checkpoint,
+                    witness_generator,
+                    r1cs.clone(),
+                    private_inputs,
+                    &pp,
+                    &checkpoint_file,
+                    checkpoint_interval,
+                )?
+            } else {
+                create_recursive_circuit_with_checkpoints_from_source(
+                    witness_generator,
+                    r1cs.clone(),
+                    private_inputs,
+                    start_public_input.clone(),
+                    &pp,
+                    &checkpoint_file,
+                    checkpoint_interval,
+                )?
+            };
+            ProofBundle::recursive(&r1cs, &pp, &output.recursive_snark, &output.z0)?
+                .save(&output_file)?;
+            println!(
+                "folded {} steps, step_out {}, wrote {}",
+                output.num_steps,
+                format_fields(&output.zn),
+                output_file.display()
+            );
+        }
+        Command::Compress {
+            circuit,
+            params,
+            proof,
+            output,
+            backend,
+            prover_key,
+        } => match backend {
+            Backend::Spartan => {
+                compress_proof::<G1, G2, Spartan>(circuit, &params, &proof, &output, &prover_key)?
+            }
+            Backend::PreprocessingSpartan => compress_proof::<G1, G2, PreprocessingSpartan>(
+                circuit,
+                &params,
+                &proof,
+                &output,
+                &prover_key,
+            )?,
+        },
+        Command::Verify {
+            proof,
+            r1cs,
+            sym,
+            params,
+            verifier_key,
+        } => {
+            let proof = ProofBundle::<G1, G2>::load(&proof)?;
+            let zn = match proof.header.kind {
+                ProofKind::Recursive => {
+                    let (r1cs, params) = r1cs.zip(params).ok_or_else(|| {
+                        NovaScotiaError::InvalidInput(
+                            "verifying a RecursiveSNARK needs --r1cs and --params".to_string(),
+                        )
+                    })?;
+                    let r1cs = load_circuit::<G1, G2>(&r1cs, sym.as_deref())?;
+                    proof.check_r1cs(&r1cs)?;
+                    let pp = load_public_params::<G1, G2>(&params, &r1cs)?;
+                    proof.verify(&pp)?
+                }
+                ProofKind::Compressed { backend } => {
+                    let verifier_key = verifier_key.ok_or_else(|| {
+                        NovaScotiaError::InvalidInput(
+                            "verifying a CompressedSNARK needs --verifier-key".to_string(),
+                        )
+                    })?;
+                    if backend == <Spartan as CompressionBackend<G1, G2>>::ID {
+                        Verifier::<G1, G2, Spartan>::load(&verifier_key)?
+                            .verify(&proof)?
+                            .zn
+                    } else if backend == <PreprocessingSpartan as CompressionBackend<G1, G2>>::ID {
+                        Verifier::<G1, G2, PreprocessingSpartan>::load(&verifier_key)?
+                            .verify(&proof)?
+                            .zn
+                    } else {
+                        return Err(NovaScotiaError::InvalidInput(format!(
+                            "proof was compressed with unsupported backend {}",
+                            backend
+                        )));
+                    }
+                }
+            };
+            println!("proof is valid, step_out {}", format_fields(&zn));
+        }
+        Command::Inspect { file } => inspect::<G1, G2>(&file)?,
+    }
+    Ok(())
+}
+
+fn read_magic(path: &Path) -> Result<[u8; 4], NovaScotiaError> {
+    let mut magic = [0u8; 4];
+    File::open(path)?.read_exact(&mut magic)?;
+    Ok(magic)
+}
+
+fn inspect<G1, G2>(path: &Path) -> Result<(), NovaScotiaError>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+    EE<G1>: EvaluationEngineTrait<G1>,
+    EE<G2>: EvaluationEngineTrait<G2>,
+{
+    match &read_magic(path)? {
+        b"r1cs" => {
+            let header = header_from_reader(BufReader::new(File::open(path)?))?;
+            let prime = BigUint::from_bytes_le(&header.prime_size);
+            println!("R1CS file");
+            match SupportedEngine::from_modulus(&prime) {
+                Some(engine) => println!("field: {} scalar field", engine),
+                None => println!("field: unknown, modulus {}", prime),
+            }
+            println!("wires: {}", header.n_wires);
+            println!("public outputs: {}", header.n_pub_out);
+            println!("public inputs: {}", header.n_pub_in);
+            println!("private inputs: {}", header.n_prv_in);
+            println!("constraints: {}", header.n_constraints);
+        }
+        b"wtns" => {
+            let mut reader = BufReader::new(File::open(path)?);
+            let mut header = [0u8; 24];
+            reader.read_exact(&mut header)?;
+            let mut field_size = [0u8; 4];
+            reader.read_exact(&mut field_size)?;
+            let field_size = u32::from_le_bytes(field_size);
+            if field_size > MAX_FIELD_SIZE {
+                return Err(NovaScotiaError::MalformedWitness(format!(
+                    "field size of {} bytes",
+                    field_size
+                )));
+            }
+            let mut prime = vec![0u8; field_size as usize];
+            reader.read_exact(&mut prime)?;
+            let witness_len = reader.read_u32::<LittleEndian>()?;
+            let prime = BigUint::from_bytes_le(&prime);
+            println!("witness file");
+            match SupportedEngine::from_modulus(&prime) {
+                Some(engine) => println!("field: {} scalar field", engine),
+                None => println!("field: unknown, modulus {}", prime),
+            }
+            println!("values: {}", witness_len);
+        }
+        b"nspp" => {
+            let header = read_params_header::<G1, G2, _>(&mut BufReader::new(File::open(path)?))?;
+            println!("public parameters, version {}", header.version);
+            println!(
+                "compression: {}",
+                match header.compression {
+                    Compression::None => "none",
+                    Compression::Gzip => "gzip",
+                }
+            );
+            println!("R1CS digest: {}", to_hex(&header.r1cs_digest));
+            println!(
+                "public parameters digest: {}",
+                to_decimal(&header.pp_digest)
+            );
+        }
+        b"nsck" => {
+            let checkpoint = Checkpoint::<G1, G2>::load(path)?;
+            println!("checkpoint of a RecursiveSNARK");
+            println!("steps: {}", checkpoint.num_steps);
+            println!("step_in: {}", format_fields(&checkpoint.z0));
+            println!("step_out: {}", format_fields(&checkpoint.zi));
+            println!(
+                "public parameters digest: {}",
+                to_decimal(&checkpoint.pp_digest)
+            );
+        }
+        bundle::MAGIC => {
+            let header = read_bundle_header::<G1, G2, _>(&mut BufReader::new(File::open(path)?))?;
+            println!("proof bundle, version {}", header.version);
+            println!(
+                "curves: {}/{}",
+                header.primary.engine_name(),
+                header.secondary.engine_name()
+            );
+            match header.kind {
+                ProofKind::Recursive => println!("proof: RecursiveSNARK"),
+                ProofKind::Compressed { backend } => {
+                    println!("proof: CompressedSNARK, compression backend {}", backend)
+                }
+            }
+            println!("R1CS digest: {}", to_hex(&header.r1cs_digest));
+            println!(
+                "public parameters digest: {}",
+                to_decimal(&header.pp_digest)
+            );
+            println!("steps: {}", header.num_steps);
+            println!("step_in: {}", format_fields(&header.z0));
+            println!("step_out: {}", format_fields(&header.zn));
+        }
+        _ => {
+            return Err(NovaScotiaError::InvalidInput(format!(
+                "{} is not a file nova-scotia knows about",
+                path.display()
+            )))
+        }
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let res = match cli.curve {
+        Curve::Pasta => run::<PallasEngine, VestaEngine>(cli.command),
+        Curve::Bn254 => run::<Bn256EngineIPA, GrumpkinEngine>(cli.command),
+    };
+    match res {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_cli(args: &[&str]) -> Result<(), NovaScotiaError> {
+        let cli = Cli::parse_from(std::iter::once("nova-scotia").chain(args.iter().copied()));
+        run::<PallasEngine, VestaEngine>(cli.command)
+    }
+
+    #[test]
+    fn test_setup_prove_compress_verify() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/toy/pasta");
+        let r1cs = root.join("toy.r1cs");
+        let r1cs = r1cs.to_str().unwrap();
+        let wasm = root.join("toy_js/toy.wasm");
+        let dir = tempfile::tempdir().unwrap();
+        let path = |name: &str| dir.path().join(name).to_str().unwrap().to_string();
+        std::fs::write(
+            path("inputs.jsonl"),
+            "{\"adder\": 0}\n{\"adder\": 1}\n\n{\"adder\": 2}\n",
+        )
+        .unwrap();
+
+        run_cli(&["setup", "--r1cs", r1cs, "-o", &path("toy.params")]).unwrap();
+        run_cli(&["inspect", &path("toy.params")]).unwrap();
+        let prove = [
+            "prove",
+            "--r1cs",
+            r1cs,
+            "--params",
+            &path("toy.params"),
+            "--witness-generator",
+            wasm.to_str().unwrap(),
+            "--inputs",
+            &path("inputs.jsonl"),
+            "--step-in",
+            "10,10",
+            "-o",
+            &path("toy.proof"),
+        ];
+        run_cli(&prove).unwrap();
+        let proof =
+            ProofBundle::<PallasEngine, VestaEngine>::load(&dir.path().join("toy.proof")).unwrap();
+        assert_eq!(proof.header.num_steps, 3);
+        // step_out[0] = step_in[0] + adder, step_out[1] = step_in[0] + step_in[1]
+        assert_eq!(
+            proof.header.zn,
+            vec![F::<PallasEngine>::from(13), F::<PallasEngine>::from(41)]
+        );
+
+        // resuming from the final checkpoint skips the three folded steps
+        std::fs::write(
+            path("more_inputs.jsonl"),
+            "{\"adder\": 0}\n{\"adder\": 1}\n{\"adder\": 2}\n{\"adder\": 3}\n",
+        )
+        .unwrap();
+        let mut resume = prove.to_vec();
+        let (more_inputs, resumed, checkpoint) = (
+            path("more_inputs.jsonl"),
+            path("resumed.proof"),
+            path("toy.proof.checkpoint"),
+        );
+        resume[8] = &more_inputs;
+        resume[12] = &resumed;
+        resume.extend(["--resume", "--checkpoint", &checkpoint]);
+        run_cli(&resume).unwrap();
+        let proof =
+            ProofBundle::<PallasEngine, VestaEngine>::load(&dir.path().join("resumed.proof"))
+                .unwrap();
+        assert_eq!(proof.header.num_steps, 4);
+        assert_eq!(
+            proof.header.zn,
+            vec![F::<PallasEngine>::from(16), F::<PallasEngine>::from(54)]
+        );
+
+        run_cli(&[
+            "verify",
+            "--proof",
+            &path("toy.proof"),
+            "--r1cs",
+            r1cs,
+            "--params",
+            &path("toy.params"),
+        ])
+        .unwrap();
+
+        let compress = [
+            "compress",
+            "--r1cs",
+            r1cs,
+            "--params",
+            &path("toy.params"),
+            "--proof",
+            &path("toy.proof"),
+            "--prover-key",
+            &path("toy.params.pk"),
+            "-o",
+            &path("toy.compressed"),
+        ];
+        run_cli(&compress).unwrap();
+        run_cli(&[
+            "verify",
+            "--proof",
+            &path("toy.compressed"),
+            "--verifier-key",
+            &path("toy.params.vk"),
+        ])
+        .unwrap();
+
+        // the prover key belongs to Spartan
+        let mut compress = compress.to_vec();
+        compress.extend(["--backend", "preprocessing-spartan"]);
+        assert!(run_cli(&compress).is_err());
+    }
+}
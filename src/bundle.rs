@@ -0,0 +1,485 @@
+// Self-describing proof bundles, for handing a proof to a verifier with no other metadata.
+//
+// A bundle file (version 1) is laid out as follows, with integers in little endian:
+//
+//   magic `nspf`
+//   u32  format version
+//   u8   primary engine id, u8 secondary engine id (see `SupportedEngine::id`)
+//   u32  size in bytes of a primary field element
+//   32   digest of the R1CS the proof is for (see `R1CS::digest`)
+//   F    digest of the public parameters
+//   u64  number of steps
+//   u32  arity, followed by `z_0` and the claimed `z_n`, one field element each
+//   u8   payload kind: 0 for a RecursiveSNARK, otherwise the id of the compression backend
+//   u64  payload length, followed by the bincode encoding of the snark
+//
+// Field elements are written in their canonical little-endian representation. Everything up to
+// the payload is read and validated before the snark is decoded.
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use ff::PrimeField;
+use nova_snark::{
+    nova::{PublicParams, RecursiveSNARK},
+    traits::Engine,
+};
+
+use crate::circom::circuit::R1CS;
+use crate::circom::prime::SupportedEngine;
+use crate::compression::{CompressedSnark, CompressionBackend, CompressionVerifierKey};
+use crate::convert::to_hex;
+use crate::error::NovaScotiaError;
+use crate::{C1, F};
+
+pub const MAGIC: &[u8; 4] = b"nspf";
+const VERSION: u32 = 1;
+
+/// What kind of snark a bundle carries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProofKind {
+    Recursive,
+    /// a CompressedSNARK made with the [`CompressionBackend`] of this id
+    Compressed {
+        backend: u8,
+    },
+}
+
+impl ProofKind {
+    fn to_byte(self) -> u8 {
+        match self {
+            ProofKind::Recursive => 0,
+            ProofKind::Compressed { backend } => backend,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Self {
+        match byte {
+            0 => ProofKind::Recursive,
+            backend => ProofKind::Compressed { backend },
+        }
+    }
+}
+
+/// Everything a bundle says about its proof, readable without decoding the snark.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BundleHeader<Fr: PrimeField> {
+    pub version: u32,
+    pub primary: SupportedEngine,
+    pub secondary: SupportedEngine,
+    pub r1cs_digest: [u8; 32],
+    pub pp_digest: Fr,
+    pub num_steps: usize,
+    pub z0: Vec<Fr>,
+    /// `z_n` claimed by the prover, checked against the snark when verifying
+    pub zn: Vec<Fr>,
+    pub kind: ProofKind,
+}
+
+/// A proof together with the header that describes it.
+pub struct ProofBundle<G1, G2>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+{
+    pub header: BundleHeader<F<G1>>,
+    payload: Vec<u8>,
+    _p: std::marker::PhantomData<G2>,
+}
+
+fn engines<G1, G2>() -> Result<(SupportedEngine, SupportedEngine), NovaScotiaError>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+{
+    SupportedEngine::of::<F<G1>>()
+        .zip(SupportedEngine::of::<F<G2>>())
+        .ok_or_else(|| {
+            NovaScotiaError::InvalidInput(
+                "proof bundles only support the engines in `SupportedEngine`".to_string(),
+            )
+        })
+}
+
+fn field_size<Fr: PrimeField>() -> usize {
+    Fr::Repr::default().as_ref().len()
+}
+
+fn write_field<Fr: PrimeField, W: Write>(writer: &mut W, value: &Fr) -> std::io::Result<()> {
+    writer.write_all(value.to_repr().as_ref())
+}
+
+fn read_field<Fr: PrimeField, R: Read>(reader: &mut R) -> Result<Fr, NovaScotiaError> {
+    let mut repr = Fr::Repr::default();
+    reader.read_exact(repr.as_mut())?;
+    Option::from(Fr::from_repr(repr)).ok_or_else(|| {
+        NovaScotiaError::NonCanonicalFieldElement(
+            "proof bundle contains a value outside the field".to_string(),
+        )
+    })
+}
+
+fn read_fields<Fr: PrimeField, R: Read>(
+    reader: &mut R,
+    len: usize,
+) -> Result<Vec<Fr>, NovaScotiaError> {
+    (0..len).map(|_| read_field(reader)).collect()
+}
+
+/// read and validate the header of a bundle for the `G1`/`G2` cycle, leaving `reader` at the
+/// start of the payload
+pub fn read_bundle_header<G1, G2, R: Read>(
+    reader: &mut R,
+) -> Result<BundleHeader<F<G1>>, NovaScotiaError>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+{
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(NovaScotiaError::InvalidInput(
+            "not a Nova Scotia proof bundle".to_string(),
+        ));
+    }
+    let version = reader.read_u32::<LittleEndian>()?;
+    if version != VERSION {
+        return Err(NovaScotiaError::InvalidInput(format!(
+            "unsupported proof bundle version {}",
+            version
+        )));
+    }
+
+    let (expected_primary, expected_secondary) = engines::<G1, G2>()?;
+    let engine = |id| {
+        SupportedEngine::from_id(id).ok_or_else(|| {
+            NovaScotiaError::InvalidInput(format!("proof bundle names unknown engine {}", id))
+        })
+    };
+    let primary = engine(reader.read_u8()?)?;
+    let secondary = engine(reader.read_u8()?)?;
+    if (primary, secondary) != (expected_primary, expected_secondary) {
+        return Err(NovaScotiaError::FieldMismatch {
+            expected: format!(
+                "{}/{} proof",
+                expected_primary.engine_name(),
+                expected_secondary.engine_name()
+            ),
+            found: format!(
+                "{}/{} proof",
+                primary.engine_name(),
+                secondary.engine_name()
+            ),
+        });
+    }
+    let size = reader.read_u32::<LittleEndian>()? as usize;
+    if size != field_size::<F<G1>>() {
+        return Err(NovaScotiaError::InvalidInput(format!(
+            "proof bundle stores {}-byte field elements, expected {}",
+            size,
+            field_size::<F<G1>>()
+        )));
+    }
+
+    let mut r1cs_digest = [0u8; 32];
+    reader.read_exact(&mut r1cs_digest)?;
+    let pp_digest = read_field(reader)?;
+    let num_steps = reader.read_u64::<LittleEndian>()? as usize;
+    let arity = reader.read_u32::<LittleEndian>()? as usize;
+    let z0 = read_fields(reader, arity)?;
+    let zn = read_fields(reader, arity)?;
+    let kind = ProofKind::from_byte(reader.read_u8()?);
+
+    Ok(BundleHeader {
+        version,
+        primary,
+        secondary,
+        r1cs_digest,
+        pp_digest,
+        num_steps,
+        z0,
+        zn,
+        kind,
+    })
+}
+
+impl<G1, G2> ProofBundle<G1, G2>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+{
+    fn new<T: serde::Serialize>(
+        r1cs: &R1CS<F<G1>>,
+        pp: &PublicParams<G1, G2, C1<G1>>,
+        recursive_snark: &RecursiveSNARK<G1, G2, C1<G1>>,
+        z0: &[F<G1>],
+        kind: ProofKind,
+        snark: &T,
+    ) -> Result<Self, NovaScotiaError> {
+        let (primary, secondary) = engines::<G1, G2>()?;
+        Ok(ProofBundle {
+            header: BundleHeader {
+                version: VERSION,
+                primary,
+                secondary,
+                r1cs_digest: r1cs.digest(),
+                pp_digest: pp.digest(),
+                num_steps: recursive_snark.num_steps(),
+                z0: z0.to_vec(),
+                zn: recursive_snark.outputs().to_vec(),
+                kind,
+            },
+            payload: bincode::serialize(snark)?,
+            _p: std::marker::PhantomData,
+        })
+    }
+
+    /// bundle a RecursiveSNARK that folded `r1cs` under `pp`, starting from `z0`
+    pub fn recursive(
+        r1cs: &R1CS<F<G1>>,
+        pp: &PublicParams<G1, G2, C1<G1>>,
+        recursive_snark: &RecursiveSNARK<G1, G2, C1<G1>>,
+        z0: &[F<G1>],
+    ) -> Result<Self, NovaScotiaError> {
+        Self::new(
+            r1cs,
+            pp,
+            recursive_snark,
+            z0,
+            ProofKind::Recursive,
+            recursive_snark,
+        )
+    }
+
+    /// bundle the compression of `recursive_snark` with backend `B`
+    pub fn compressed<B>(
+        r1cs: &R1CS<F<G1>>,
+        pp: &PublicParams<G1, G2, C1<G1>>,
+        recursive_snark: &RecursiveSNARK<G1, G2, C1<G1>>,
+        z0: &[F<G1>],
+        compressed_snark: &CompressedSnark<G1, G2, B>,
+    ) -> Result<Self, NovaScotiaError>
+    where
+        B: CompressionBackend<G1, G2>,
+    {
+        Self::new(
+            r1cs,
+            pp,
+            recursive_snark,
+            z0,
+            ProofKind::Compressed { backend: B::ID },
+            compressed_snark,
+        )
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), NovaScotiaError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, NovaScotiaError> {
+        Self::read(BufReader::new(File::open(path)?))
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> Result<(), NovaScotiaError> {
+        let header = &self.header;
+        writer.write_all(MAGIC)?;
+        writer.write_u32::<LittleEndian>(header.version)?;
+        writer.write_u8(header.primary.id())?;
+        writer.write_u8(header.secondary.id())?;
+        writer.write_u32::<LittleEndian>(field_size::<F<G1>>() as u32)?;
+        writer.write_all(&header.r1cs_digest)?;
+        write_field(&mut writer, &header.pp_digest)?;
+        writer.write_u64::<LittleEndian>(header.num_steps as u64)?;
+        writer.write_u32::<LittleEndian>(header.z0.len() as u32)?;
+        for value in header.z0.iter().chain(&header.zn) {
+            write_field(&mut writer, value)?;
+        }
+        writer.write_u8(header.kind.to_byte())?;
+        writer.write_u64::<LittleEndian>(self.payload.len() as u64)?;
+        writer.write_all(&self.payload)?;
+        Ok(())
+    }
+
+    /// read a bundle, validating its header before reading the payload
+    pub fn read<R: Read>(mut reader: R) -> Result<Self, NovaScotiaError> {
+        let header = read_bundle_header::<G1, G2, _>(&mut reader)?;
+        let len = reader.read_u64::<LittleEndian>()?;
+        let mut payload = vec![];
+        reader.take(len).read_to_end(&mut payload)?;
+        if payload.len() as u64 != len {
+            return Err(NovaScotiaError::InvalidInput(
+                "proof bundle is truncated".to_string(),
+            ));
+        }
+        Ok(ProofBundle {
+            header,
+            payload,
+            _p: std::marker::PhantomData,
+        })
+    }
+
+    /// check that the bundle is a proof for `r1cs`
+    pub fn check_r1cs(&self, r1cs: &R1CS<F<G1>>) -> Result<(), NovaScotiaError> {
+        let expected = r1cs.digest();
+        if self.header.r1cs_digest != expected {
+            return Err(NovaScotiaError::DigestMismatch(format!(
+                "proof is for R1CS {}, not {}",
+                to_hex(&self.header.r1cs_digest),
+                to_hex(&expected)
+            )));
+        }
+        Ok(())
+    }
+
+    /// check that the bundle was proven with the public parameters `pp`
+    pub fn check_params(&self, pp: &PublicParams<G1, G2, C1<G1>>) -> Result<(), NovaScotiaError> {
+        let expected = pp.digest();
+        if self.header.pp_digest != expected {
+            return Err(NovaScotiaError::DigestMismatch(format!(
+                "proof was made with public parameters {:?}, not {:?}",
+                self.header.pp_digest, expected
+            )));
+        }
+        Ok(())
+    }
+
+    fn check_kind(&self, kind: ProofKind) -> Result<(), NovaScotiaError> {
+        if self.header.kind != kind {
+            return Err(NovaScotiaError::InvalidInput(format!(
+                "proof bundle holds a {:?} proof, not {:?}",
+                self.header.kind, kind
+            )));
+        }
+        Ok(())
+    }
+
+    // the snark must verify to the `z_n` the header claims
+    fn check_outputs(&self, zn: Vec<F<G1>>) -> Result<Vec<F<G1>>, NovaScotiaError> {
+        if zn != self.header.zn {
+            return Err(NovaScotiaError::InvalidInput(
+                "verified z_n does not match the one claimed by the proof bundle".to_string(),
+            ));
+        }
+        Ok(zn)
+    }
+
+    /// decode the RecursiveSNARK of a recursive bundle
+    pub fn recursive_snark(&self) -> Result<RecursiveSNARK<G1, G2, C1<G1>>, NovaScotiaError> {
+        self.check_kind(ProofKind::Recursive)?;
+        Ok(bincode::deserialize(&self.payload)?)
+    }
+
+    /// decode the CompressedSNARK of a bundle compressed with backend `B`
+    pub fn compressed_snark<B>(&self) -> Result<CompressedSnark<G1, G2, B>, NovaScotiaError>
+    where
+        B: CompressionBackend<G1, G2>,
+    {
+        self.check_kind(ProofKind::Compressed { backend: B::ID })?;
+        Ok(bincode::deserialize(&self.payload)?)
+    }
+
+    /// verify a recursive bundle against the public parameters `pp`, returning `z_n`
+    pub fn verify(&self, pp: &PublicParams<G1, G2, C1<G1>>) -> Result<Vec<F<G1>>, NovaScotiaError> {
+        self.check_params(pp)?;
+        let recursive_snark = self.recursive_snark()?;
+        let zn = recursive_snark.verify(pp, self.header.num_steps, &self.header.z0)?;
+        self.check_outputs(zn)
+    }
+
+    /// verify a bundle compressed with backend `B`, returning `z_n`
+    pub fn verify_compressed<B>(
+        &self,
+        vk: &CompressionVerifierKey<G1, G2, B>,
+    ) -> Result<Vec<F<G1>>, NovaScotiaError>
+    where
+        B: CompressionBackend<G1, G2>,
+    {
+        let compressed_snark = self.compressed_snark::<B>()?;
+        let zn = compressed_snark.verify(vk, self.header.num_steps, &self.header.z0)?;
+        self.check_outputs(zn)
+    }
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::circom::circuit::CircomCircuit;
+    use crate::circom::reader::load_r1cs;
+    use crate::compression::Spartan;
+    use crate::{create_public_params, FileLocation};
+    use ff::Field;
+    use nova_snark::provider::{Bn256EngineIPA, GrumpkinEngine, PallasEngine, VestaEngine};
+    use std::sync::Arc;
+
+    type G1 = PallasEngine;
+    type G2 = VestaEngine;
+
+    #[test]
+    fn test_recursive_bundle() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let r1cs = load_r1cs::<G1, G2>(&FileLocation::PathBuf(
+            root.join("examples/toy/pasta/toy.r1cs"),
+        ))
+        .unwrap();
+        let pp = create_public_params::<G1, G2>(r1cs.clone()).unwrap();
+
+        let z0 = vec![F::<G1>::from(10), F::<G1>::from(10)];
+        let circuit = CircomCircuit {
+            r1cs: Arc::new(r1cs.clone()),
+            witness: Some(vec![
+                F::<G1>::ONE,
+                F::<G1>::from(10),
+                F::<G1>::from(20),
+                F::<G1>::from(10),
+                F::<G1>::from(10),
+            ]),
+        };
+        let mut recursive_snark = RecursiveSNARK::new(&pp, &circuit, &z0).unwrap();
+        recursive_snark.prove_step(&pp, &circuit).unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("toy.proof");
+        ProofBundle::recursive(&r1cs, &pp, &recursive_snark, &z0)
+            .unwrap()
+            .save(&path)
+            .unwrap();
+
+        let bundle = ProofBundle::<G1, G2>::load(&path).unwrap();
+        let header = &bundle.header;
+        assert_eq!(header.primary, SupportedEngine::Pallas);
+        assert_eq!(header.secondary, SupportedEngine::Vesta);
+        assert_eq!(header.num_steps, 1);
+        assert_eq!(header.z0, z0);
+        assert_eq!(header.zn, vec![F::<G1>::from(10), F::<G1>::from(20)]);
+        assert_eq!(header.kind, ProofKind::Recursive);
+        bundle.check_r1cs(&r1cs).unwrap();
+        assert_eq!(bundle.verify(&pp).unwrap(), header.zn);
+        assert!(bundle.compressed_snark::<Spartan>().is_err());
+
+        let mut other_r1cs = r1cs.clone();
+        other_r1cs.io.step_in.swap(0, 1);
+        assert!(matches!(
+            bundle.check_r1cs(&other_r1cs),
+            Err(NovaScotiaError::DigestMismatch(_))
+        ));
+
+        // a prover claiming a different z_n is caught
+        let mut forged = ProofBundle::<G1, G2>::load(&path).unwrap();
+        forged.header.zn[1] = F::<G1>::from(21);
+        assert!(forged.verify(&pp).is_err());
+
+        // the header is rejected for another curve cycle before the snark is decoded
+        assert!(matches!(
+            ProofBundle::<Bn256EngineIPA, GrumpkinEngine>::load(&path),
+            Err(NovaScotiaError::FieldMismatch { .. })
+        ));
+
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes.truncate(bytes.len() - 1);
+        assert!(ProofBundle::<G1, G2>::read(&bytes[..]).is_err());
+    }
+}
@@ -0,0 +1,167 @@
+// On-disk checkpoints of a RecursiveSNARK that is still being folded.
+//
+// A checkpoint file is the magic `nsck`, a little-endian u32 format version and the bincode
+// encoding of a `Checkpoint`.
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use nova_snark::{
+    nova::{PublicParams, RecursiveSNARK},
+    traits::Engine,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::error::NovaScotiaError;
+use crate::{write_atomically, C1, F};
+
+const MAGIC: &[u8; 4] = b"nsck";
+const VERSION: u32 = 1;
+
+/// Everything needed to continue folding a [`RecursiveSNARK`] after a restart.
+#[derive(Deserialize)]
+#[serde(bound = "")]
+pub struct Checkpoint<G1, G2>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+{
+    /// digest of the public parameters, which covers the R1CS the snark folds
+    pub pp_digest: F<G1>,
+    pub num_steps: usize,
+    pub z0: Vec<F<G1>>,
+    pub zi: Vec<F<G1>>,
+    pub recursive_snark: RecursiveSNARK<G1, G2, C1<G1>>,
+}
+
+// borrowed view of a `Checkpoint` with the same encoding, so saving does not clone the snark
+#[derive(Serialize)]
+#[serde(bound = "")]
+struct CheckpointRef<'a, G1, G2>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+{
+    pp_digest: F<G1>,
+    num_steps: usize,
+    z0: &'a [F<G1>],
+    zi: &'a [F<G1>],
+    recursive_snark: &'a RecursiveSNARK<G1, G2, C1<G1>>,
+}
+
+/// save a checkpoint of `recursive_snark`, which started folding from `z0` under `pp`
+pub fn save_checkpoint<G1, G2>(
+    path: &Path,
+    recursive_snark: &RecursiveSNARK<G1, G2, C1<G1>>,
+    z0: &[F<G1>],
+    pp: &PublicParams<G1, G2, C1<G1>>,
+) -> Result<(), NovaScotiaError>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+{
+    write_checkpoint(
+        path,
+        &CheckpointRef {
+            pp_digest: pp.digest(),
+            num_steps: recursive_snark.num_steps(),
+            z0,
+            zi: recursive_snark.outputs(),
+            recursive_snark,
+        },
+    )
+}
+
+fn write_checkpoint<G1, G2>(
+    path: &Path,
+    checkpoint: &CheckpointRef<'_, G1, G2>,
+) -> Result<(), NovaScotiaError>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+{
+    write_atomically(path, |writer| {
+        writer.write_all(MAGIC)?;
+        writer.write_u32::<LittleEndian>(VERSION)?;
+        bincode::serialize_into(writer, checkpoint)?;
+        Ok(())
+    })
+}
+
+impl<G1, G2> Checkpoint<G1, G2>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+{
+    pub fn new(
+        recursive_snark: RecursiveSNARK<G1, G2, C1<G1>>,
+        z0: Vec<F<G1>>,
+        pp: &PublicParams<G1, G2, C1<G1>>,
+    ) -> Self {
+        Checkpoint {
+            pp_digest: pp.digest(),
+            num_steps: recursive_snark.num_steps(),
+            zi: recursive_snark.outputs().to_vec(),
+            z0,
+            recursive_snark,
+        }
+    }
+
+    /// write the checkpoint to `path`, atomically replacing any previous checkpoint
+    pub fn save(&self, path: &Path) -> Result<(), NovaScotiaError> {
+        write_checkpoint(
+            path,
+            &CheckpointRef {
+                pp_digest: self.pp_digest,
+                num_steps: self.num_steps,
+                z0: &self.z0,
+                zi: &self.zi,
+                recursive_snark: &self.recursive_snark,
+            },
+        )
+    }
+
+    /// read a checkpoint written by [`Checkpoint::save`]
+    pub fn load(path: &Path) -> Result<Self, NovaScotiaError> {
+        Self::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self, NovaScotiaError> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(NovaScotiaError::InvalidInput(
+                "not a Nova Scotia checkpoint".to_string(),
+            ));
+        }
+        let version = reader.read_u32::<LittleEndian>()?;
+        if version != VERSION {
+            return Err(NovaScotiaError::InvalidInput(format!(
+                "unsupported checkpoint version {}",
+                version
+            )));
+        }
+        let checkpoint: Self = bincode::deserialize_from(reader)?;
+        if checkpoint.num_steps != checkpoint.recursive_snark.num_steps()
+            || checkpoint.zi != checkpoint.recursive_snark.outputs()
+        {
+            return Err(NovaScotiaError::InvalidInput(
+                "checkpoint metadata does not match its snark".to_string(),
+            ));
+        }
+        Ok(checkpoint)
+    }
+
+    /// check that the checkpoint was taken with the given public parameters
+    pub fn check_params(&self, pp: &PublicParams<G1, G2, C1<G1>>) -> Result<(), NovaScotiaError> {
+        let pp_digest = pp.digest();
+        if self.pp_digest != pp_digest {
+            return Err(NovaScotiaError::DigestMismatch(format!(
+                "checkpoint was taken with public parameters {:?}, but resuming with {:?}",
+                self.pp_digest, pp_digest
+            )));
+        }
+        Ok(())
+    }
+}
@@ -1,3 +1,11 @@
 pub mod circuit;
 pub mod file;
+#[cfg(feature = "supernova")]
+pub mod nonuniform;
+pub mod prime;
+#[cfg(feature = "prover")]
 pub mod reader;
+pub mod sym;
+#[cfg(feature = "prover")]
+pub mod wasm;
+pub mod writer;
@@ -1,23 +1,36 @@
 use nova_snark::frontend::{
-    num::AllocatedNum,
-    ConstraintSystem, LinearCombination, SynthesisError
+    num::AllocatedNum, ConstraintSystem, LinearCombination, SynthesisError,
 };
 use nova_snark::traits::circuit::StepCircuit;
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
+use sha3::{Digest, Sha3_256};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
 use std::str;
+use std::sync::Arc;
 
+use crate::circom::sym::SymbolTable;
+use crate::convert::to_decimal;
+use crate::error::NovaScotiaError;
 use ff::PrimeField;
 
+/// an R1CS exported with `snarkjs r1cs export json`, each constraint being the `[A, B, C]`
+/// linear combinations as maps from wire index to decimal coefficient
 #[derive(Serialize, Deserialize)]
 pub struct CircuitJson {
     pub constraints: Vec<Vec<BTreeMap<String, String>>>,
+    /// public inputs, not counting the outputs or the constant wire
     #[serde(rename = "nPubInputs")]
     pub num_inputs: usize,
     #[serde(rename = "nOutputs")]
     pub num_outputs: usize,
     #[serde(rename = "nVars")]
     pub num_variables: usize,
+    /// modulus of the field, in decimal
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prime: Option<String>,
+    #[serde(default, rename = "useCustomGates")]
+    pub use_custom_gates: bool,
 }
 
 pub type Constraint<Fr> = (Vec<(usize, Fr)>, Vec<(usize, Fr)>, Vec<(usize, Fr)>);
@@ -25,39 +38,288 @@ pub type Constraint<Fr> = (Vec<(usize, Fr)>, Vec<(usize, Fr)>, Vec<(usize, Fr)>)
 #[derive(Clone)]
 pub struct R1CS<Fr: PrimeField> {
     pub num_inputs: usize,
+    /// how many of the public signals after the constant one are circom's public outputs, which
+    /// it numbers before the public inputs
+    pub num_outputs: usize,
     pub num_aux: usize,
     pub num_variables: usize,
     pub constraints: Vec<Constraint<Fr>>,
+    pub io: IoLayout,
+}
+
+/// Witness indices of the `step_in` and `step_out` signals of a step circuit.
+///
+/// Any other signal, public or not, is an ordinary witness value that is fed through the
+/// per-step private inputs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IoLayout {
+    pub step_in: Vec<usize>,
+    pub step_out: Vec<usize>,
+}
+
+impl IoLayout {
+    /// the layout circom produces for `component main { public [step_in] }` when `step_out` and
+    /// `step_in` are the only public signals: all outputs first, followed by all inputs
+    pub fn symmetric(num_inputs: usize) -> Self {
+        let arity = num_inputs.saturating_sub(1) / 2;
+        IoLayout {
+            step_out: (1..=arity).collect(),
+            step_in: (arity + 1..=2 * arity).collect(),
+        }
+    }
+
+    /// locate `main.step_in[*]` and `main.step_out[*]` by name in a circom symbol file
+    pub fn from_symbols(
+        symbols: &SymbolTable,
+        num_variables: usize,
+    ) -> Result<Self, NovaScotiaError> {
+        let step_in = symbols.array_witnesses("main.step_in")?;
+        let step_out = symbols.array_witnesses("main.step_out")?;
+        if step_in.is_empty() {
+            return Err(NovaScotiaError::InvalidInput(
+                "main.step_in not found in symbols".to_string(),
+            ));
+        }
+        if step_in.len() != step_out.len() {
+            return Err(NovaScotiaError::InvalidInput(format!(
+                "arity mismatch: {} step_in signals but {} step_out signals",
+                step_in.len(),
+                step_out.len()
+            )));
+        }
+        if let Some(w) = step_in
+            .iter()
+            .chain(step_out.iter())
+            .find(|&&w| w == 0 || w >= num_variables)
+        {
+            return Err(NovaScotiaError::InvalidInput(format!(
+                "IO signal at witness index {} is outside of the circuit's {} variables",
+                w, num_variables
+            )));
+        }
+        Ok(IoLayout { step_in, step_out })
+    }
+
+    pub fn arity(&self) -> usize {
+        self.step_in.len()
+    }
+}
+
+impl<Fr: PrimeField> R1CS<Fr> {
+    /// replace the default IO layout with one read from the circuit's symbol file
+    pub fn with_symbols(mut self, symbols: &SymbolTable) -> Result<Self, NovaScotiaError> {
+        self.io = IoLayout::from_symbols(symbols, self.num_variables)?;
+        Ok(self)
+    }
+
+    /// SHA3-256 digest of the constraint system and its IO layout, identifying the circuit
+    /// that artifacts such as public parameters were produced for
+    pub fn digest(&self) -> [u8; 32] {
+        let mut hasher = Sha3_256::new();
+        let mut usize_le = |n: usize| hasher.update((n as u64).to_le_bytes());
+        usize_le(self.num_inputs);
+        usize_le(self.num_aux);
+        usize_le(self.num_variables);
+        usize_le(self.constraints.len());
+        for constraint in &self.constraints {
+            for lc in [&constraint.0, &constraint.1, &constraint.2] {
+                hasher.update((lc.len() as u64).to_le_bytes());
+                for (index, coeff) in lc {
+                    hasher.update((*index as u64).to_le_bytes());
+                    hasher.update(coeff.to_repr().as_ref());
+                }
+            }
+        }
+        for signals in [&self.io.step_in, &self.io.step_out] {
+            hasher.update((signals.len() as u64).to_le_bytes());
+            for &w in signals {
+                hasher.update((w as u64).to_le_bytes());
+            }
+        }
+        hasher.finalize().into()
+    }
+}
+
+/// Signal names of a circuit's wires, for reporting on a witness.
+#[derive(Debug, Clone, Default)]
+pub struct WireNames {
+    names: HashMap<usize, String>,
 }
 
+impl WireNames {
+    /// name each wire through the R1CS file's wire-to-label map and the labels of the circuit's
+    /// `.sym` file
+    pub fn new(symbols: &SymbolTable, wire_mapping: &[u64]) -> Self {
+        let names = wire_mapping
+            .iter()
+            .enumerate()
+            .filter_map(|(wire, &label)| {
+                symbols
+                    .by_label(label)
+                    .map(|symbol| (wire, symbol.name.clone()))
+            })
+            .collect();
+        WireNames { names }
+    }
+
+    /// name each wire by the witness indices of the circuit's `.sym` file alone
+    pub fn from_symbols(symbols: &SymbolTable) -> Self {
+        let names = symbols
+            .symbols
+            .iter()
+            .filter_map(|symbol| symbol.witness.map(|w| (w, symbol.name.clone())))
+            .collect();
+        WireNames { names }
+    }
+
+    pub fn get(&self, wire: usize) -> Option<&str> {
+        self.names.get(&wire).map(String::as_str)
+    }
+}
+
+/// A constraint `A * B = C` that a witness does not satisfy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsatisfiedConstraint<Fr: PrimeField> {
+    /// index of the constraint in the R1CS file
+    pub index: usize,
+    pub a: Fr,
+    pub b: Fr,
+    pub c: Fr,
+    /// every wire the constraint reads, with its signal name if known and its witness value
+    pub wires: Vec<(usize, Option<String>, Fr)>,
+}
+
+impl<Fr: PrimeField> fmt::Display for UnsatisfiedConstraint<Fr> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "constraint {}: A = {}, B = {}, A * B = {}, but C = {}",
+            self.index,
+            to_decimal(&self.a),
+            to_decimal(&self.b),
+            to_decimal(&(self.a * self.b)),
+            to_decimal(&self.c)
+        )?;
+        for (wire, name, value) in &self.wires {
+            write!(
+                f,
+                "\n    {} (wire {}) = {}",
+                name.as_deref().unwrap_or("<unnamed>"),
+                wire,
+                to_decimal(value)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+// how many unsatisfied constraints `check_witness` describes in its error
+const MAX_REPORTED_CONSTRAINTS: usize = 10;
+
 #[derive(Clone)]
 pub struct CircomCircuit<Fr: PrimeField> {
-    pub r1cs: R1CS<Fr>,
+    // shared between the circuits of all steps
+    pub r1cs: Arc<R1CS<Fr>>,
     pub witness: Option<Vec<Fr>>,
     // debug symbols
 }
 
-impl<'a, Fr: PrimeField> CircomCircuit<Fr> {
+impl<Fr: PrimeField> CircomCircuit<Fr> {
+    pub fn io(&self) -> &IoLayout {
+        &self.r1cs.io
+    }
+
     pub fn get_public_outputs(&self) -> Vec<Fr> {
-        // NOTE: assumes exactly half of the (public inputs + outputs) are outputs
-        let pub_output_count = (self.r1cs.num_inputs - 1) / 2;
-        let mut z_out: Vec<Fr> = vec![];
-        for i in 1..self.r1cs.num_inputs {
-            // Public inputs do not exist, so we alloc, and later enforce equality from z values
-            let f: Fr = {
-                match &self.witness {
-                    None => Fr::ONE,
-                    Some(w) => w[i],
-                }
-            };
+        self.io()
+            .step_out
+            .iter()
+            .map(|&i| match &self.witness {
+                None => Fr::ONE,
+                Some(w) => w[i],
+            })
+            .collect()
+    }
 
-            if i <= pub_output_count {
-                // public output
-                z_out.push(f);
+    /// every constraint the witness does not satisfy, in order
+    pub fn unsatisfied_constraints(
+        &self,
+        names: Option<&WireNames>,
+    ) -> Result<Vec<UnsatisfiedConstraint<Fr>>, NovaScotiaError> {
+        let witness = self.witness.as_ref().ok_or_else(|| {
+            NovaScotiaError::InvalidInput("the circuit has no witness to check".to_string())
+        })?;
+        if witness.len() != self.r1cs.num_variables {
+            return Err(NovaScotiaError::InvalidInput(format!(
+                "witness has {} values, but the circuit has {} wires",
+                witness.len(),
+                self.r1cs.num_variables
+            )));
+        }
+        if witness[0] != Fr::ONE {
+            return Err(NovaScotiaError::InvalidInput(
+                "wire 0 of the witness must be 1".to_string(),
+            ));
+        }
+
+        let eval = |lc: &[(usize, Fr)]| {
+            lc.iter().fold(Fr::ZERO, |acc, (index, coeff)| {
+                acc + *coeff * witness[*index]
+            })
+        };
+        let mut unsatisfied = vec![];
+        for (index, (a, b, c)) in self.r1cs.constraints.iter().enumerate() {
+            let (a_value, b_value, c_value) = (eval(a), eval(b), eval(c));
+            if a_value * b_value == c_value {
+                continue;
             }
+            let mut wires = a
+                .iter()
+                .chain(b)
+                .chain(c)
+                .map(|(wire, _)| *wire)
+                .filter(|&wire| wire != 0)
+                .collect::<Vec<_>>();
+            wires.sort_unstable();
+            wires.dedup();
+            unsatisfied.push(UnsatisfiedConstraint {
+                index,
+                a: a_value,
+                b: b_value,
+                c: c_value,
+                wires: wires
+                    .into_iter()
+                    .map(|wire| {
+                        let name = names.and_then(|names| names.get(wire)).map(str::to_string);
+                        (wire, name, witness[wire])
+                    })
+                    .collect(),
+            });
         }
+        Ok(unsatisfied)
+    }
 
-        z_out
+    /// check the witness against every constraint, so that a bad witness is caught with a
+    /// description of the failing constraints before it is folded
+    pub fn check_witness(&self, names: Option<&WireNames>) -> Result<(), NovaScotiaError> {
+        let unsatisfied = self.unsatisfied_constraints(names)?;
+        if unsatisfied.is_empty() {
+            return Ok(());
+        }
+        let mut report = unsatisfied
+            .iter()
+            .take(MAX_REPORTED_CONSTRAINTS)
+            .map(|constraint| constraint.to_string())
+            .collect::<Vec<_>>();
+        if unsatisfied.len() > MAX_REPORTED_CONSTRAINTS {
+            report.push(format!(
+                "and {} more",
+                unsatisfied.len() - MAX_REPORTED_CONSTRAINTS
+            ));
+        }
+        Err(NovaScotiaError::UnsatisfiedWitness {
+            count: unsatisfied.len(),
+            report: report.join("\n"),
+        })
     }
 
     pub fn vanilla_synthesize<CS: ConstraintSystem<Fr>>(
@@ -65,91 +327,145 @@ impl<'a, Fr: PrimeField> CircomCircuit<Fr> {
         cs: &mut CS,
         z: &[AllocatedNum<Fr>],
     ) -> Result<Vec<AllocatedNum<Fr>>, SynthesisError> {
-        // println!("witness: {:?}", self.witness);
-        // // println!("wire_mapping: {:?}", self.wire_mapping);
-        // // println!("aux_offset: {:?}", self.aux_offset);
-        // println!("num_inputs: {:?}", self.r1cs.num_inputs);
-        // println!("num_aux: {:?}", self.r1cs.num_aux);
-        // println!("num_variables: {:?}", self.r1cs.num_variables);
-        // println!("constraints: {:?}", self.r1cs.constraints);
-        // println!(
-        //     "z: {:?}",
-        //     z.into_iter().map(|x| x.get_value()).collect::<Vec<_>>()
-        // );
-
-        let witness = &self.witness;
-
-        let mut vars: Vec<AllocatedNum<Fr>> = vec![];
-        let mut z_out: Vec<AllocatedNum<Fr>> = vec![];
-        let pub_output_count = (self.r1cs.num_inputs - 1) / 2;
-
-        for i in 1..self.r1cs.num_inputs {
-            // Public inputs do not exist, so we alloc, and later enforce equality from z values
-            let f: Fr = {
-                match witness {
-                    None => Fr::ONE,
-                    Some(w) => w[i],
-                }
-            };
-            let v = AllocatedNum::alloc(cs.namespace(|| format!("public_{}", i)), || Ok(f))?;
-
-            vars.push(v.clone());
-            if i <= pub_output_count {
-                // public output
-                z_out.push(v);
-            }
+        let io = self.io();
+        if z.len() != io.arity() {
+            return Err(SynthesisError::Unsatisfiable);
         }
-        for i in 0..self.r1cs.num_aux {
-            // Private witness trace
-            let f: Fr = {
-                match witness {
-                    None => Fr::ONE,
-                    Some(w) => w[i + self.r1cs.num_inputs],
-                }
-            };
+        let vars = synthesize_r1cs(
+            &mut NovaSynthesizer(cs),
+            &self.r1cs,
+            |i| match &self.witness {
+                None => Fr::ONE,
+                Some(w) => w[i],
+            },
+            z,
+        )?;
+        Ok(io.step_out.iter().map(|&w| vars[w - 1].clone()).collect())
+    }
+}
 
-            let v = AllocatedNum::alloc(cs.namespace(|| format!("aux_{}", i)), || Ok(f))?;
-            vars.push(v);
-        }
+/// The operations [`synthesize_r1cs`] needs from a constraint system, so that Nova's frontend and
+/// bellpepper, which SuperNova uses, share one synthesis of circom's constraints.
+pub(crate) trait R1CSSynthesizer<Fr: PrimeField> {
+    type Num: Clone;
+    type Error;
 
-        let make_lc = |lc_data: Vec<(usize, Fr)>| {
-            let res = lc_data.iter().fold(
-                LinearCombination::<Fr>::zero(),
-                |lc: LinearCombination<Fr>, (index, coeff)| {
-                    lc + if *index > 0 {
-                        (*coeff, vars[*index - 1].get_variable())
-                    } else {
-                        (*coeff, CS::one())
-                    }
-                },
-            );
-            res
-        };
-        for (i, constraint) in self.r1cs.constraints.iter().enumerate() {
-            cs.enforce(
-                || format!("constraint {}", i),
-                |_| make_lc(constraint.0.clone()),
-                |_| make_lc(constraint.1.clone()),
-                |_| make_lc(constraint.2.clone()),
-            );
-        }
+    fn alloc(&mut self, name: String, value: Fr) -> Result<Self::Num, Self::Error>;
 
-        for i in (pub_output_count + 1)..self.r1cs.num_inputs {
-            cs.enforce(
-                || format!("pub input enforce {}", i),
-                |lc| lc + z[i - 1 - pub_output_count].get_variable(),
-                |lc| lc + CS::one(),
-                |lc| lc + vars[i - 1].get_variable(),
-            );
-        }
+    /// enforce `a * b = c`, with wire 0 of the linear combinations the constant one and wire `i`
+    /// the variable `vars[i - 1]`
+    fn enforce(
+        &mut self,
+        name: String,
+        vars: &[Self::Num],
+        a: &[(usize, Fr)],
+        b: &[(usize, Fr)],
+        c: &[(usize, Fr)],
+    );
+
+    fn enforce_equal(&mut self, name: String, a: &Self::Num, b: &Self::Num);
+}
+
+/// allocate every wire of `r1cs` after the constant one, with values `value(i)`, enforce its
+/// constraints and bind its `step_in` wires to `z`, whose length the caller has checked
+///
+/// Returns the allocated wires, wire `i` at index `i - 1`.
+pub(crate) fn synthesize_r1cs<Fr: PrimeField, S: R1CSSynthesizer<Fr>>(
+    cs: &mut S,
+    r1cs: &R1CS<Fr>,
+    value: impl Fn(usize) -> Fr,
+    z: &[S::Num],
+) -> Result<Vec<S::Num>, S::Error> {
+    let mut vars = Vec::with_capacity(r1cs.num_variables.saturating_sub(1));
+    // public inputs do not exist, so we alloc, and later enforce equality from z values
+    for i in 1..r1cs.num_inputs {
+        vars.push(cs.alloc(format!("public_{}", i), value(i))?);
+    }
+    // private witness trace
+    for i in 0..r1cs.num_aux {
+        vars.push(cs.alloc(format!("aux_{}", i), value(i + r1cs.num_inputs))?);
+    }
 
-        Ok(z_out)
+    for (i, constraint) in r1cs.constraints.iter().enumerate() {
+        cs.enforce(
+            format!("constraint {}", i),
+            &vars,
+            &constraint.0,
+            &constraint.1,
+            &constraint.2,
+        );
     }
+    for (i, (z_i, &w)) in z.iter().zip(r1cs.io.step_in.iter()).enumerate() {
+        cs.enforce_equal(format!("pub input enforce {}", i), z_i, &vars[w - 1]);
+    }
+    Ok(vars)
+}
+
+// Nova's frontend and bellpepper define copies of the same constraint system traits, so implement
+// `R1CSSynthesizer` for a wrapper `$synthesizer` around either from the names of their
+// `ConstraintSystem`, `AllocatedNum`, `LinearCombination` and `SynthesisError`
+macro_rules! impl_r1cs_synthesizer {
+    ($synthesizer:ident, $cs:ident, $num:ident, $lc:ident, $error:ident) => {
+        struct $synthesizer<'a, CS>(&'a mut CS);
+
+        impl<Fr: ::ff::PrimeField, CS: $cs<Fr>> $crate::circom::circuit::R1CSSynthesizer<Fr>
+            for $synthesizer<'_, CS>
+        {
+            type Num = $num<Fr>;
+            type Error = $error;
+
+            fn alloc(&mut self, name: String, value: Fr) -> Result<Self::Num, Self::Error> {
+                $num::alloc(self.0.namespace(|| name), || Ok(value))
+            }
+
+            fn enforce(
+                &mut self,
+                name: String,
+                vars: &[Self::Num],
+                a: &[(usize, Fr)],
+                b: &[(usize, Fr)],
+                c: &[(usize, Fr)],
+            ) {
+                let make_lc = |lc_data: &[(usize, Fr)]| {
+                    lc_data
+                        .iter()
+                        .fold($lc::<Fr>::zero(), |lc: $lc<Fr>, (index, coeff)| {
+                            lc + if *index > 0 {
+                                (*coeff, vars[*index - 1].get_variable())
+                            } else {
+                                (*coeff, CS::one())
+                            }
+                        })
+                };
+                self.0
+                    .enforce(|| name, |_| make_lc(a), |_| make_lc(b), |_| make_lc(c));
+            }
+
+            fn enforce_equal(&mut self, name: String, a: &Self::Num, b: &Self::Num) {
+                self.0.enforce(
+                    || name,
+                    |lc| lc + a.get_variable(),
+                    |lc| lc + CS::one(),
+                    |lc| lc + b.get_variable(),
+                );
+            }
+        }
+    };
 }
+#[cfg(feature = "supernova")]
+pub(crate) use impl_r1cs_synthesizer;
 
-impl<'a, Fr: PrimeField> StepCircuit<Fr> for CircomCircuit<Fr> {
+impl_r1cs_synthesizer!(
+    NovaSynthesizer,
+    ConstraintSystem,
+    AllocatedNum,
+    LinearCombination,
+    SynthesisError
+);
+
+impl<Fr: PrimeField> StepCircuit<Fr> for CircomCircuit<Fr> {
     fn arity(&self) -> usize {
-        (self.r1cs.num_inputs - 1) / 2
+        self.io().arity()
     }
 
     fn synthesize<CS: ConstraintSystem<Fr>>(
@@ -158,8 +474,131 @@ impl<'a, Fr: PrimeField> StepCircuit<Fr> for CircomCircuit<Fr> {
         z: &[AllocatedNum<Fr>],
     ) -> Result<Vec<AllocatedNum<Fr>>, SynthesisError> {
         // synthesize the circuit
-        let z_out = self.vanilla_synthesize(cs, z);
+        self.vanilla_synthesize(cs, z)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ff::Field;
+    use nova_snark::frontend::util_cs::test_cs::TestConstraintSystem;
+
+    type Fr = <nova_snark::provider::PallasEngine as nova_snark::traits::Engine>::Scalar;
+
+    // step_out[0] <== step_in[0] + salt, with `salt` an extra public input
+    // that circom places between the outputs and `step_in`
+    const SALTED_SYM: &str = "1,1,0,main.step_out[0]\n2,2,0,main.salt\n3,3,0,main.step_in[0]\n";
+
+    fn salted_r1cs() -> R1CS<Fr> {
+        R1CS {
+            num_inputs: 4,
+            num_outputs: 1,
+            num_aux: 0,
+            num_variables: 4,
+            constraints: vec![(
+                vec![(3, Fr::ONE), (2, Fr::ONE)],
+                vec![(0, Fr::ONE)],
+                vec![(1, Fr::ONE)],
+            )],
+            io: IoLayout::symmetric(4),
+        }
+    }
+
+    #[test]
+    fn test_io_layout_from_symbols() {
+        let symbols = SymbolTable::from_reader(SALTED_SYM.as_bytes()).unwrap();
+        let r1cs = salted_r1cs().with_symbols(&symbols).unwrap();
+        assert_eq!(
+            r1cs.io,
+            IoLayout {
+                step_in: vec![3],
+                step_out: vec![1],
+            }
+        );
+
+        let circuit = CircomCircuit {
+            r1cs: Arc::new(r1cs),
+            witness: Some(vec![Fr::ONE, Fr::from(12), Fr::from(5), Fr::from(7)]),
+        };
+        assert_eq!(circuit.arity(), 1);
+        assert_eq!(circuit.get_public_outputs(), vec![Fr::from(12)]);
+
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        let z = vec![AllocatedNum::alloc(cs.namespace(|| "z0"), || Ok(Fr::from(7))).unwrap()];
+        let z_out = circuit.synthesize(&mut cs, &z).unwrap();
+        assert!(cs.is_satisfied());
+        assert_eq!(z_out[0].get_value(), Some(Fr::from(12)));
+
+        // a `step_in` that does not match the witness is rejected
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        let z = vec![AllocatedNum::alloc(cs.namespace(|| "z0"), || Ok(Fr::from(5))).unwrap()];
+        circuit.synthesize(&mut cs, &z).unwrap();
+        assert!(!cs.is_satisfied());
+    }
+
+    #[test]
+    fn test_check_witness() {
+        let symbols = SymbolTable::from_reader(SALTED_SYM.as_bytes()).unwrap();
+        let r1cs = Arc::new(salted_r1cs().with_symbols(&symbols).unwrap());
+        let names = WireNames::from_symbols(&symbols);
+
+        let circuit = CircomCircuit {
+            r1cs: r1cs.clone(),
+            witness: Some(vec![Fr::ONE, Fr::from(12), Fr::from(5), Fr::from(7)]),
+        };
+        circuit.check_witness(Some(&names)).unwrap();
+
+        let circuit = CircomCircuit {
+            r1cs: r1cs.clone(),
+            witness: Some(vec![Fr::ONE, Fr::from(13), Fr::from(5), Fr::from(7)]),
+        };
+        let unsatisfied = circuit.unsatisfied_constraints(Some(&names)).unwrap();
+        assert_eq!(unsatisfied.len(), 1);
+        assert_eq!(unsatisfied[0].index, 0);
+        assert_eq!(
+            unsatisfied[0].wires[0],
+            (1, Some("main.step_out[0]".to_string()), Fr::from(13))
+        );
+        assert_eq!(
+            circuit
+                .check_witness(Some(&names))
+                .err()
+                .unwrap()
+                .to_string(),
+            "witness does not satisfy 1 constraints:\n\
+             constraint 0: A = 12, B = 1, A * B = 12, but C = 13\n    \
+             main.step_out[0] (wire 1) = 13\n    \
+             main.salt (wire 2) = 5\n    \
+             main.step_in[0] (wire 3) = 7"
+        );
+
+        // the same names through circom's wire-to-label map, where wire 2 is unnamed
+        let names = WireNames::new(&symbols, &[0, 1, 7, 3]);
+        let unsatisfied = circuit.unsatisfied_constraints(Some(&names)).unwrap();
+        assert_eq!(unsatisfied[0].wires[1].1, None);
+        assert_eq!(
+            unsatisfied[0].wires[2].1.as_deref(),
+            Some("main.step_in[0]")
+        );
+
+        let circuit = CircomCircuit {
+            r1cs,
+            witness: Some(vec![Fr::ONE, Fr::from(12)]),
+        };
+        assert!(circuit.check_witness(None).is_err());
+    }
 
-        z_out
+    #[test]
+    fn test_io_layout_arity_mismatch() {
+        let symbols = SymbolTable::from_reader(
+            "1,1,0,main.step_out[0]\n2,2,0,main.step_in[0]\n3,3,0,main.step_in[1]\n".as_bytes(),
+        )
+        .unwrap();
+        let err = salted_r1cs().with_symbols(&symbols).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "invalid input: arity mismatch: 2 step_in signals but 1 step_out signals"
+        );
     }
 }
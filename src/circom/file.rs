@@ -1,15 +1,18 @@
 // some codes borrowed from https://github.com/poma/zkutil/blob/master/src/r1cs_reader.rs
-#![allow(unused_variables, dead_code)]
 
-use crate::circom::circuit::Constraint;
+use crate::circom::circuit::{Constraint, IoLayout, R1CS};
+use crate::circom::prime::check_prime;
+use crate::error::NovaScotiaError;
 use byteorder::{LittleEndian, ReadBytesExt};
 use ff::PrimeField;
 use nova_snark::traits::Engine;
 use std::{
     collections::HashMap,
-    io::{Error, ErrorKind, Read, Result, Seek, SeekFrom},
+    io::{Read, Seek, SeekFrom},
 };
 
+type Result<T> = std::result::Result<T, NovaScotiaError>;
+
 // R1CSFile's header
 #[derive(Debug, Default)]
 pub struct Header {
@@ -23,6 +26,21 @@ pub struct Header {
     pub n_constraints: u32,
 }
 
+/// A PLONK custom gate template declared by a circuit compiled with custom templates.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustomGate<Fr: PrimeField> {
+    pub template_name: String,
+    pub parameters: Vec<Fr>,
+}
+
+/// An application of a [`CustomGate`] to some of the circuit's signals.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustomGateUse {
+    /// index into [`R1CSFile::custom_gates`]
+    pub id: u32,
+    pub signals: Vec<u64>,
+}
+
 // R1CSFile parse result
 #[derive(Debug, Default)]
 pub struct R1CSFile<Fr: PrimeField> {
@@ -30,28 +48,90 @@ pub struct R1CSFile<Fr: PrimeField> {
     pub header: Header,
     pub constraints: Vec<Constraint<Fr>>,
     pub wire_mapping: Vec<u64>,
+    pub custom_gates: Vec<CustomGate<Fr>>,
+    pub custom_gate_uses: Vec<CustomGateUse>,
 }
 
-pub(crate) fn read_field<R: Read, Fr: PrimeField>(mut reader: R) -> Result<Fr> {
+impl<Fr: PrimeField> R1CSFile<Fr> {
+    /// fail if the circuit applies custom gates, which are enforced outside of the R1CS
+    /// constraints: folding only the constraints would leave the gates unchecked
+    pub fn check_no_custom_gates(&self) -> Result<()> {
+        if self.custom_gate_uses.is_empty() {
+            return Ok(());
+        }
+        let mut templates = self
+            .custom_gate_uses
+            .iter()
+            .map(|gate| self.custom_gates[gate.id as usize].template_name.as_str())
+            .collect::<Vec<_>>();
+        templates.sort_unstable();
+        templates.dedup();
+        Err(NovaScotiaError::UnsupportedCustomGates(format!(
+            "{} applications of {}",
+            self.custom_gate_uses.len(),
+            templates.join(", ")
+        )))
+    }
+
+    /// the constraint system of the file, with `step_out` and `step_in` laid out as in
+    /// [`IoLayout::symmetric`]
+    pub fn into_r1cs(self) -> Result<R1CS<Fr>> {
+        self.check_no_custom_gates()?;
+        let num_outputs = self.header.n_pub_out as usize;
+        let num_inputs = 1 + self.header.n_pub_in as usize + num_outputs;
+        let num_variables = self.header.n_wires as usize;
+        let num_aux = num_variables.checked_sub(num_inputs).ok_or_else(|| {
+            NovaScotiaError::MalformedR1CS("fewer wires than public signals".to_string())
+        })?;
+        Ok(R1CS {
+            num_aux,
+            num_inputs,
+            num_outputs,
+            num_variables,
+            constraints: self.constraints,
+            io: IoLayout::symmetric(num_inputs),
+        })
+    }
+}
+
+/// read a little-endian field element of `field_size` bytes, the width a circom file declares
+/// for its prime, which need not match the length of `Fr`'s representation
+pub(crate) fn read_field<R: Read, Fr: PrimeField>(mut reader: R, field_size: usize) -> Result<Fr> {
     let mut repr = Fr::ZERO.to_repr();
-    for digit in repr.as_mut().iter_mut() {
-        // TODO: may need to reverse order?
-        *digit = reader.read_u8()?;
+    let len = repr.as_ref().len().min(field_size);
+    reader.read_exact(&mut repr.as_mut()[..len])?;
+    // bytes beyond the representation must be zero for the value to fit in the field
+    let mut overflow = false;
+    for _ in len..field_size {
+        overflow |= reader.read_u8()? != 0;
+    }
+    if overflow {
+        return Err(NovaScotiaError::NonCanonicalFieldElement(format!(
+            "{}-byte value does not fit in the target field",
+            field_size
+        )));
     }
-    let fr = Fr::from_repr(repr).unwrap();
-    Ok(fr)
+    Option::from(Fr::from_repr(repr)).ok_or_else(|| {
+        NovaScotiaError::NonCanonicalFieldElement(format!(
+            "0x{}",
+            repr.as_ref()
+                .iter()
+                .rev()
+                .map(|b| format!("{:02x}", b))
+                .collect::<String>()
+        ))
+    })
 }
 
 fn read_header<R: Read>(mut reader: R, size: u64) -> Result<Header> {
     let field_size = reader.read_u32::<LittleEndian>()?;
-    let mut prime_size = vec![0u8; field_size as usize];
-    reader.read_exact(&mut prime_size)?;
     if size != 32 + field_size as u64 {
-        return Err(Error::new(
-            ErrorKind::InvalidData,
-            "Invalid header section size",
+        return Err(NovaScotiaError::MalformedR1CS(
+            "Invalid header section size".to_string(),
         ));
     }
+    let mut prime_size = vec![0u8; field_size as usize];
+    reader.read_exact(&mut prime_size)?;
 
     Ok(Header {
         field_size,
@@ -69,136 +149,391 @@ fn read_constraint_vec<R: Read, Fr: PrimeField>(
     mut reader: R,
     header: &Header,
 ) -> Result<Vec<(usize, Fr)>> {
-    let n_vec = reader.read_u32::<LittleEndian>()? as usize;
-    let mut vec = Vec::with_capacity(n_vec);
+    let n_vec = reader.read_u32::<LittleEndian>()?;
+    // the count is not checked against the section yet, so nothing is reserved up front
+    let mut vec = vec![];
     for _ in 0..n_vec {
+        let wire = reader.read_u32::<LittleEndian>()?;
+        if wire >= header.n_wires {
+            return Err(NovaScotiaError::MalformedR1CS(format!(
+                "Constraint refers to wire {} outside of the circuit",
+                wire
+            )));
+        }
         vec.push((
-            reader.read_u32::<LittleEndian>()? as usize,
-            read_field::<&mut R, Fr>(&mut reader)?,
+            wire as usize,
+            read_field::<&mut R, Fr>(&mut reader, header.field_size as usize)?,
         ));
     }
     Ok(vec)
 }
 
-fn read_constraints<R: Read, Fr: PrimeField>(
+fn read_constraint<R: Read, Fr: PrimeField>(
     mut reader: R,
+    header: &Header,
+) -> Result<Constraint<Fr>> {
+    Ok((
+        read_constraint_vec::<&mut R, Fr>(&mut reader, header)?,
+        read_constraint_vec::<&mut R, Fr>(&mut reader, header)?,
+        read_constraint_vec::<&mut R, Fr>(&mut reader, header)?,
+    ))
+}
+
+// the most constraints a section of `size` bytes can hold, each being at least the term counts of
+// its three linear combinations
+fn max_constraints(size: u64) -> u32 {
+    (size / 12).try_into().unwrap_or(u32::MAX)
+}
+
+fn read_constraints<R: Read, Fr: PrimeField>(
+    reader: R,
     size: u64,
     header: &Header,
 ) -> Result<Vec<Constraint<Fr>>> {
-    // todo check section size
-    let mut vec = Vec::with_capacity(header.n_constraints as usize);
+    // read no further than the section, which must hold exactly `n_constraints` constraints
+    let mut section = reader.take(size);
+    let mut vec = Vec::with_capacity(header.n_constraints.min(max_constraints(size)) as usize);
     for _ in 0..header.n_constraints {
-        vec.push((
-            read_constraint_vec::<&mut R, Fr>(&mut reader, header)?,
-            read_constraint_vec::<&mut R, Fr>(&mut reader, header)?,
-            read_constraint_vec::<&mut R, Fr>(&mut reader, header)?,
-        ));
+        let constraint =
+            read_constraint::<_, Fr>(&mut section, header).map_err(|err| match err {
+                NovaScotiaError::Io(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => {
+                    NovaScotiaError::MalformedR1CS("constraint section is truncated".to_string())
+                }
+                err => err,
+            })?;
+        vec.push(constraint);
+    }
+    if section.limit() != 0 {
+        return Err(NovaScotiaError::MalformedR1CS(format!(
+            "{} bytes after the last constraint",
+            section.limit()
+        )));
     }
     Ok(vec)
 }
 
 fn read_map<R: Read>(mut reader: R, size: u64, header: &Header) -> Result<Vec<u64>> {
     if size != header.n_wires as u64 * 8 {
-        return Err(Error::new(
-            ErrorKind::InvalidData,
-            "Invalid map section size",
+        return Err(NovaScotiaError::MalformedR1CS(
+            "Invalid map section size".to_string(),
         ));
     }
+    // `size` is within the file, see `read_sections`
     let mut vec = Vec::with_capacity(header.n_wires as usize);
     for _ in 0..header.n_wires {
         vec.push(reader.read_u64::<LittleEndian>()?);
     }
-    if vec[0] != 0 {
-        return Err(Error::new(
-            ErrorKind::InvalidData,
-            "Wire 0 should always be mapped to 0",
+    if vec.first() != Some(&0) {
+        return Err(NovaScotiaError::MalformedR1CS(
+            "Wire 0 should always be mapped to 0".to_string(),
         ));
     }
     Ok(vec)
 }
 
-pub fn from_reader<R: Read + Seek, G1, G2>(mut reader: R) -> Result<R1CSFile<<G1 as Engine>::Scalar>>
-where
-    G1: Engine<Base = <G2 as Engine>::Scalar>,
-    G2: Engine<Base = <G1 as Engine>::Scalar>,
-{
+fn read_string<R: Read>(mut reader: R) -> Result<String> {
+    let mut bytes = vec![];
+    loop {
+        match reader.read_u8()? {
+            0 => break,
+            byte => bytes.push(byte),
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| {
+        NovaScotiaError::MalformedR1CS("Custom gate name is not valid UTF-8".to_string())
+    })
+}
+
+fn read_custom_gates<R: Read, Fr: PrimeField>(
+    mut reader: R,
+    header: &Header,
+) -> Result<Vec<CustomGate<Fr>>> {
+    let n_gates = reader.read_u32::<LittleEndian>()?;
+    let mut gates = vec![];
+    for _ in 0..n_gates {
+        let template_name = read_string(&mut reader)?;
+        let n_parameters = reader.read_u32::<LittleEndian>()?;
+        let parameters = (0..n_parameters)
+            .map(|_| read_field::<&mut R, Fr>(&mut reader, header.field_size as usize))
+            .collect::<Result<_>>()?;
+        gates.push(CustomGate {
+            template_name,
+            parameters,
+        });
+    }
+    Ok(gates)
+}
+
+fn read_custom_gate_uses<R: Read>(
+    mut reader: R,
+    header: &Header,
+    n_gates: usize,
+) -> Result<Vec<CustomGateUse>> {
+    let n_uses = reader.read_u32::<LittleEndian>()?;
+    let mut uses = vec![];
+    for _ in 0..n_uses {
+        let id = reader.read_u32::<LittleEndian>()?;
+        if id as usize >= n_gates {
+            return Err(NovaScotiaError::MalformedR1CS(format!(
+                "Application of undeclared custom gate {}",
+                id
+            )));
+        }
+        let n_signals = reader.read_u32::<LittleEndian>()?;
+        let mut signals = vec![];
+        for _ in 0..n_signals {
+            let signal = reader.read_u64::<LittleEndian>()?;
+            if signal >= header.n_wires as u64 {
+                return Err(NovaScotiaError::MalformedR1CS(format!(
+                    "Custom gate applied to signal {} outside of the circuit",
+                    signal
+                )));
+            }
+            signals.push(signal);
+        }
+        uses.push(CustomGateUse { id, signals });
+    }
+    Ok(uses)
+}
+
+// section type -> (file offset, section size)
+type Sections = HashMap<u32, (u64, u64)>;
+
+pub(crate) const HEADER_TYPE: u32 = 1;
+pub(crate) const CONSTRAINT_TYPE: u32 = 2;
+pub(crate) const WIRE2LABEL_TYPE: u32 = 3;
+// emitted by circom 2.0.6+ for circuits with `custom_templates`
+pub(crate) const CUSTOM_GATES_LIST_TYPE: u32 = 4;
+pub(crate) const CUSTOM_GATES_USES_TYPE: u32 = 5;
+
+fn read_sections<R: Read + Seek>(mut reader: R) -> Result<(u32, Sections)> {
     let mut magic = [0u8; 4];
     reader.read_exact(&mut magic)?;
     if magic != [0x72, 0x31, 0x63, 0x73] {
         // magic = "r1cs"
-        return Err(Error::new(ErrorKind::InvalidData, "Invalid magic number"));
+        return Err(NovaScotiaError::MalformedR1CS(
+            "Invalid magic number".to_string(),
+        ));
     }
 
     let version = reader.read_u32::<LittleEndian>()?;
     if version != 1 {
-        return Err(Error::new(ErrorKind::InvalidData, "Unsupported version"));
+        return Err(NovaScotiaError::MalformedR1CS(
+            "Unsupported version".to_string(),
+        ));
     }
 
     let num_sections = reader.read_u32::<LittleEndian>()?;
 
-    // section type -> file offset
-    let mut section_offsets = HashMap::<u32, u64>::new();
-    let mut section_sizes = HashMap::<u32, u64>::new();
+    let mut sections = Sections::new();
 
-    // get file offset of each section
+    // get file offset of each section, which must lie within the file, so that the sizes can
+    // bound what is allocated while reading the sections
+    let start = reader.stream_position()?;
+    let end = reader.seek(SeekFrom::End(0))?;
+    reader.seek(SeekFrom::Start(start))?;
     for _ in 0..num_sections {
         let section_type = reader.read_u32::<LittleEndian>()?;
         let section_size = reader.read_u64::<LittleEndian>()?;
-        let offset = reader.seek(SeekFrom::Current(0))?;
-        section_offsets.insert(section_type, offset);
-        section_sizes.insert(section_type, section_size);
-        reader.seek(SeekFrom::Current(section_size as i64))?;
+        let offset = reader.stream_position()?;
+        let section_end = offset
+            .checked_add(section_size)
+            .filter(|&section_end| section_end <= end)
+            .ok_or_else(|| {
+                NovaScotiaError::MalformedR1CS(format!(
+                    "section {} extends past the end of the file",
+                    section_type
+                ))
+            })?;
+        sections.insert(section_type, (offset, section_size));
+        reader.seek(SeekFrom::Start(section_end))?;
+    }
+
+    Ok((version, sections))
+}
+
+fn section(sections: &Sections, section_type: u32, name: &str) -> Result<(u64, u64)> {
+    sections
+        .get(&section_type)
+        .copied()
+        .ok_or_else(|| NovaScotiaError::MalformedR1CS(format!("Missing {} section", name)))
+}
+
+/// read only the header of an R1CS file
+pub fn header_from_reader<R: Read + Seek>(mut reader: R) -> Result<Header> {
+    let (_, sections) = read_sections(&mut reader)?;
+    let (header_offset, header_size) = section(&sections, HEADER_TYPE, "header")?;
+    reader.seek(SeekFrom::Start(header_offset))?;
+    read_header(&mut reader, header_size)
+}
+
+pub fn from_reader<R: Read + Seek, G1, G2>(reader: R) -> Result<R1CSFile<<G1 as Engine>::Scalar>>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+{
+    read_r1cs_file(reader)
+}
+
+/// parse an R1CS file whose prime is the modulus of `Fr`
+pub(crate) fn read_r1cs_file<R: Read + Seek, Fr: PrimeField>(reader: R) -> Result<R1CSFile<Fr>> {
+    read_r1cs_file_with(reader, |reader, offset, size, header| {
+        reader.seek(SeekFrom::Start(offset))?;
+        read_constraints::<&mut R, Fr>(reader, size, header)
+    })
+}
+
+/// parse an in-memory R1CS file, typically memory-mapped, decoding its constraints in parallel
+///
+/// One pass over the constraint section records where each constraint starts, after which the
+/// constraints are decoded independently on the rayon thread pool.
+#[cfg(feature = "prover")]
+pub fn from_bytes<Fr: PrimeField>(bytes: &[u8]) -> Result<R1CSFile<Fr>> {
+    read_r1cs_file_with(std::io::Cursor::new(bytes), |_, offset, size, header| {
+        let section = usize::try_from(offset)
+            .ok()
+            .zip(usize::try_from(size).ok())
+            .and_then(|(offset, size)| bytes.get(offset..offset.checked_add(size)?))
+            .ok_or_else(|| {
+                NovaScotiaError::MalformedR1CS("constraint section is truncated".to_string())
+            })?;
+        read_constraints_parallel(section, header)
+    })
+}
+
+/// byte offset of every constraint in a constraint section
+#[cfg(feature = "prover")]
+fn constraint_offsets(section: &[u8], header: &Header) -> Result<Vec<usize>> {
+    let term_size = 4 + header.field_size as usize;
+    let truncated =
+        || NovaScotiaError::MalformedR1CS("constraint section is truncated".to_string());
+    let mut offsets = Vec::with_capacity(
+        header
+            .n_constraints
+            .min(max_constraints(section.len() as u64)) as usize,
+    );
+    let mut position = 0usize;
+    for _ in 0..header.n_constraints {
+        offsets.push(position);
+        for _ in 0..3 {
+            let n_terms = section.get(position..position + 4).ok_or_else(truncated)?;
+            let n_terms = u32::from_le_bytes(n_terms.try_into().unwrap()) as usize;
+            position = n_terms
+                .checked_mul(term_size)
+                .and_then(|terms| terms.checked_add(position + 4))
+                .filter(|&end| end <= section.len())
+                .ok_or_else(truncated)?;
+        }
+    }
+    if position != section.len() {
+        return Err(NovaScotiaError::MalformedR1CS(format!(
+            "{} bytes after the last constraint",
+            section.len() - position
+        )));
     }
+    Ok(offsets)
+}
 
-    let header_type = 1;
-    let constraint_type = 2;
-    let wire2label_type = 3;
+#[cfg(feature = "prover")]
+fn read_constraints_parallel<Fr: PrimeField>(
+    section: &[u8],
+    header: &Header,
+) -> Result<Vec<Constraint<Fr>>> {
+    use rayon::prelude::*;
+
+    let offsets = constraint_offsets(section, header)?;
+    let mut constraints = vec![Constraint::<Fr>::default(); offsets.len()];
+    constraints
+        .par_iter_mut()
+        .zip(offsets.par_iter())
+        .try_for_each(|(constraint, &offset)| {
+            *constraint = read_constraint::<_, Fr>(&section[offset..], header)?;
+            Ok::<_, NovaScotiaError>(())
+        })?;
+    Ok(constraints)
+}
 
-    reader.seek(SeekFrom::Start(*section_offsets.get(&header_type).unwrap()))?;
-    let header = read_header(&mut reader, *section_sizes.get(&header_type).unwrap())?;
-    if header.field_size != 32 {
-        return Err(Error::new(
-            ErrorKind::InvalidData,
-            "This parser only supports 32-byte fields",
+// parse an R1CS file, leaving the constraint section to `read_constraints`, which is handed the
+// reader, the section's offset and size, and the header
+fn read_r1cs_file_with<R, Fr, F>(mut reader: R, read_constraints: F) -> Result<R1CSFile<Fr>>
+where
+    R: Read + Seek,
+    Fr: PrimeField,
+    F: FnOnce(&mut R, u64, u64, &Header) -> Result<Vec<Constraint<Fr>>>,
+{
+    let (version, sections) = read_sections(&mut reader)?;
+
+    let (header_offset, header_size) = section(&sections, HEADER_TYPE, "header")?;
+    let (constraint_offset, constraint_size) = section(&sections, CONSTRAINT_TYPE, "constraint")?;
+    let (wire2label_offset, wire2label_size) =
+        section(&sections, WIRE2LABEL_TYPE, "wire to label map")?;
+
+    reader.seek(SeekFrom::Start(header_offset))?;
+    let header = read_header(&mut reader, header_size)?;
+    if header.field_size == 0 {
+        return Err(NovaScotiaError::MalformedR1CS(
+            "field size is 0 bytes".to_string(),
         ));
     }
+    check_prime::<Fr>(&header.prime_size)?;
+
+    let constraints = read_constraints(&mut reader, constraint_offset, constraint_size, &header)?;
 
-    // println!("header: {:?}", header);
-    // if header.prime_size != hex!("010000f093f5e1439170b97948e833285d588181b64550b829a031e1724e6430")
-    // {
-    //     return Err(Error::new(
-    //         ErrorKind::InvalidData,
-    //         "This parser only supports bn256",
-    //     ));
-    // }
-
-    reader.seek(SeekFrom::Start(
-        *section_offsets.get(&constraint_type).unwrap(),
-    ))?;
-    let constraints = read_constraints::<&mut R, <G1 as Engine>::Scalar>(
-        &mut reader,
-        *section_sizes.get(&constraint_type).unwrap(),
-        &header,
-    )?;
-
-    reader.seek(SeekFrom::Start(
-        *section_offsets.get(&wire2label_type).unwrap(),
-    ))?;
-    let wire_mapping = read_map(
-        &mut reader,
-        *section_sizes.get(&wire2label_type).unwrap(),
-        &header,
-    )?;
+    reader.seek(SeekFrom::Start(wire2label_offset))?;
+    let wire_mapping = read_map(&mut reader, wire2label_size, &header)?;
+
+    let (custom_gates, custom_gate_uses) = match (
+        sections.get(&CUSTOM_GATES_LIST_TYPE),
+        sections.get(&CUSTOM_GATES_USES_TYPE),
+    ) {
+        (None, None) => (vec![], vec![]),
+        (Some(&(list_offset, _)), Some(&(uses_offset, _))) => {
+            reader.seek(SeekFrom::Start(list_offset))?;
+            let gates = read_custom_gates::<&mut R, Fr>(&mut reader, &header)?;
+            reader.seek(SeekFrom::Start(uses_offset))?;
+            let uses = read_custom_gate_uses(&mut reader, &header, gates.len())?;
+            (gates, uses)
+        }
+        _ => {
+            return Err(NovaScotiaError::MalformedR1CS(
+                "Custom gates list and applications must appear together".to_string(),
+            ))
+        }
+    };
 
     Ok(R1CSFile {
         version,
         header,
         constraints,
         wire_mapping,
+        custom_gates,
+        custom_gate_uses,
     })
 }
 
+#[cfg(test)]
 mod tests {
+    // a 64-bit prime, which circom writes as 8-byte field elements while its `PrimeField`
+    // representation is 16 bytes long
+    #[derive(ff::PrimeField)]
+    #[PrimeFieldModulus = "18446744069414584321"]
+    #[PrimeFieldGenerator = "7"]
+    #[PrimeFieldReprEndianness = "little"]
+    pub(crate) struct Goldilocks([u64; 2]);
+
+    // an R1CS file made of the given sections
+    fn r1cs_bytes(sections: Vec<(u32, Vec<u8>)>) -> Vec<u8> {
+        let mut data = b"r1cs".to_vec();
+        data.extend(1u32.to_le_bytes());
+        data.extend((sections.len() as u32).to_le_bytes());
+        for (section_type, section) in sections {
+            data.extend(section_type.to_le_bytes());
+            data.extend((section.len() as u64).to_le_bytes());
+            data.extend(section);
+        }
+        data
+    }
+
     #[test]
     fn sample() {
         use super::*;
@@ -257,8 +592,8 @@ mod tests {
     "
         );
 
-        type G1 = nova_snark::provider::PallasEngine;
-        type G2 = nova_snark::provider::VestaEngine;
+        type G1 = nova_snark::provider::Bn256EngineIPA;
+        type G2 = nova_snark::provider::GrumpkinEngine;
 
         let reader = BufReader::new(Cursor::new(&data[..]));
         let file = from_reader::<_, G1, G2>(reader).unwrap();
@@ -296,6 +631,325 @@ mod tests {
         let mut buf: Vec<u8> = 32_u32.to_le_bytes().to_vec();
         buf.resize(4 + 32, 0);
         let err = read_header(&mut buf.as_slice(), 32).err().unwrap();
-        assert_eq!(err.kind(), ErrorKind::InvalidData)
+        assert!(matches!(err, NovaScotiaError::MalformedR1CS(_)))
+    }
+
+    #[test]
+    fn test_huge_counts() {
+        use super::*;
+        use crate::circom::prime::modulus;
+        use std::io::Cursor;
+
+        type Fr = <nova_snark::provider::PallasEngine as Engine>::Scalar;
+        let r1cs = |n_wires: u32, n_constraints: u32| {
+            let mut header = 32u32.to_le_bytes().to_vec();
+            header.extend(modulus::<Fr>().to_bytes_le());
+            for n in [n_wires, 1, 1, 0] {
+                header.extend(n.to_le_bytes());
+            }
+            header.extend(3u64.to_le_bytes());
+            header.extend(n_constraints.to_le_bytes());
+            let wires = (0..3u64).flat_map(|label| label.to_le_bytes()).collect();
+            r1cs_bytes(vec![(1, header), (2, vec![0; 12]), (3, wires)])
+        };
+        assert_eq!(
+            read_r1cs_file::<_, Fr>(Cursor::new(r1cs(3, 1)))
+                .unwrap()
+                .constraints,
+            vec![(vec![], vec![], vec![])]
+        );
+
+        // counts far beyond what the file holds are rejected without reserving memory for them
+        for (n_wires, n_constraints) in [(3, u32::MAX), (u32::MAX, 1)] {
+            let bytes = r1cs(n_wires, n_constraints);
+            assert!(matches!(
+                read_r1cs_file::<_, Fr>(Cursor::new(&bytes)),
+                Err(NovaScotiaError::MalformedR1CS(_))
+            ));
+            #[cfg(feature = "prover")]
+            assert!(matches!(
+                from_bytes::<Fr>(&bytes),
+                Err(NovaScotiaError::MalformedR1CS(_))
+            ));
+        }
+
+        // and so is a section claiming to be larger than the file
+        let mut bytes = r1cs(3, 1);
+        bytes[16..24].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(matches!(
+            read_r1cs_file::<_, Fr>(Cursor::new(bytes)),
+            Err(NovaScotiaError::MalformedR1CS(_))
+        ));
+    }
+
+    #[test]
+    fn test_missing_section() {
+        use super::*;
+        use std::io::Cursor;
+
+        // magic, version 1, no sections
+        let mut data = b"r1cs".to_vec();
+        data.extend(1u32.to_le_bytes());
+        data.extend(0u32.to_le_bytes());
+
+        type G1 = nova_snark::provider::PallasEngine;
+        type G2 = nova_snark::provider::VestaEngine;
+        let err = from_reader::<_, G1, G2>(Cursor::new(data)).err().unwrap();
+        assert!(matches!(err, NovaScotiaError::MalformedR1CS(_)))
+    }
+
+    #[test]
+    fn test_prime_mismatch() {
+        use super::*;
+        use std::fs::File;
+        use std::path::Path;
+
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/toy/pasta/toy.r1cs");
+
+        type G1 = nova_snark::provider::PallasEngine;
+        type G2 = nova_snark::provider::VestaEngine;
+        assert!(from_reader::<_, G1, G2>(File::open(&path).unwrap()).is_ok());
+
+        type H1 = nova_snark::provider::Bn256EngineIPA;
+        type H2 = nova_snark::provider::GrumpkinEngine;
+        let err = from_reader::<_, H1, H2>(File::open(&path).unwrap())
+            .err()
+            .unwrap();
+        assert!(matches!(err, NovaScotiaError::FieldMismatch { .. }));
+        assert_eq!(
+            err.to_string(),
+            "field mismatch: expected Bn256EngineKZG/Bn256EngineIPA (circom --prime bn128) scalar field, found PallasEngine (circom --prime vesta) scalar field"
+        );
+    }
+
+    #[test]
+    fn test_into_r1cs() {
+        use super::*;
+        use std::io::Cursor;
+        use std::path::Path;
+
+        type Fr = <nova_snark::provider::PallasEngine as Engine>::Scalar;
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/toy/pasta/toy.r1cs");
+        let bytes = std::fs::read(path).unwrap();
+
+        let r1cs = read_r1cs_file::<_, Fr>(Cursor::new(&bytes))
+            .unwrap()
+            .into_r1cs()
+            .unwrap();
+        assert_eq!(r1cs.num_inputs, 5);
+        assert_eq!(r1cs.num_outputs, 2);
+        assert_eq!(r1cs.num_aux, r1cs.num_variables - 5);
+
+        // a header claiming more public signals than wires
+        let mut file = read_r1cs_file::<_, Fr>(Cursor::new(&bytes)).unwrap();
+        file.header.n_pub_in = u32::MAX;
+        assert!(matches!(
+            file.into_r1cs(),
+            Err(NovaScotiaError::MalformedR1CS(_))
+        ));
+    }
+
+    #[cfg(feature = "prover")]
+    #[test]
+    fn test_from_bytes() {
+        use super::*;
+        use std::io::Cursor;
+        use std::path::Path;
+
+        type Fr = <nova_snark::provider::PallasEngine as Engine>::Scalar;
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/toy/pasta/toy.r1cs");
+        let mut bytes = std::fs::read(path).unwrap();
+        let expected = read_r1cs_file::<_, Fr>(Cursor::new(&bytes)).unwrap();
+        let file = from_bytes::<Fr>(&bytes).unwrap();
+        assert_eq!(file.constraints, expected.constraints);
+        assert_eq!(file.wire_mapping, expected.wire_mapping);
+        assert_eq!(file.header.n_constraints, expected.header.n_constraints);
+
+        // the constraint section must hold exactly `n_constraints` constraints; the header
+        // section follows the 120-byte constraint section and ends with `n_constraints`
+        let n_constraints_offset = 12 + 12 + 120 + 12 + 60;
+        for claimed in [
+            expected.header.n_constraints + 1,
+            expected.header.n_constraints - 1,
+        ] {
+            bytes[n_constraints_offset..n_constraints_offset + 4]
+                .copy_from_slice(&claimed.to_le_bytes());
+            assert!(matches!(
+                from_bytes::<Fr>(&bytes),
+                Err(NovaScotiaError::MalformedR1CS(_))
+            ));
+            assert!(matches!(
+                read_r1cs_file::<_, Fr>(Cursor::new(&bytes)),
+                Err(NovaScotiaError::MalformedR1CS(_))
+            ));
+        }
+        bytes[n_constraints_offset..n_constraints_offset + 4]
+            .copy_from_slice(&expected.header.n_constraints.to_le_bytes());
+
+        // the first term of the first constraint follows the section headers and its term count
+        let first_wire_offset = 12 + 12 + 4;
+        bytes[first_wire_offset..first_wire_offset + 4]
+            .copy_from_slice(&expected.header.n_wires.to_le_bytes());
+        assert!(matches!(
+            from_bytes::<Fr>(&bytes),
+            Err(NovaScotiaError::MalformedR1CS(_))
+        ));
+        assert!(matches!(
+            read_r1cs_file::<_, Fr>(Cursor::new(&bytes)),
+            Err(NovaScotiaError::MalformedR1CS(_))
+        ));
+    }
+
+    #[test]
+    fn test_narrow_field() {
+        use super::*;
+        use ff::Field;
+        use std::io::Cursor;
+
+        const P: u64 = 0xffff_ffff_0000_0001;
+        // `width`-byte little-endian encoding of `value`
+        let le = |value: u64, width: usize| {
+            let mut bytes = value.to_le_bytes().to_vec();
+            bytes.resize(width, 0);
+            bytes
+        };
+        // r1cs with `width`-byte field elements and the constraint `(2 w1) * w2 = w3 + (p - 1)`
+        let r1cs = |width: usize, last_coeff: u64| {
+            let mut header = (width as u32).to_le_bytes().to_vec();
+            header.extend(le(P, width));
+            for n in [4u32, 1, 1, 1] {
+                header.extend(n.to_le_bytes());
+            }
+            header.extend(4u64.to_le_bytes());
+            header.extend(1u32.to_le_bytes());
+
+            let mut constraints = vec![];
+            for lc in [
+                vec![(1u32, 2u64)],
+                vec![(2, 1)],
+                vec![(3, 1), (0, last_coeff)],
+            ] {
+                constraints.extend((lc.len() as u32).to_le_bytes());
+                for (wire, coeff) in lc {
+                    constraints.extend(wire.to_le_bytes());
+                    constraints.extend(le(coeff, width));
+                }
+            }
+
+            let mut wires = vec![];
+            for label in 0..4u64 {
+                wires.extend(label.to_le_bytes());
+            }
+
+            r1cs_bytes(vec![(1, header), (2, constraints), (3, wires)])
+        };
+
+        // circom sizes field elements in whole 64-bit words, but nothing in the format needs that
+        for width in [8, 9] {
+            let file = read_r1cs_file::<_, Goldilocks>(Cursor::new(r1cs(width, P - 1))).unwrap();
+            assert_eq!(file.header.field_size, width as u32);
+            let (a, b, c) = &file.constraints[0];
+            assert_eq!(a, &vec![(1, Goldilocks::from(2))]);
+            assert_eq!(b, &vec![(2, Goldilocks::from(1))]);
+            assert_eq!(c, &vec![(3, Goldilocks::ONE), (0, -Goldilocks::ONE)]);
+        }
+
+        // a coefficient that is not reduced modulo the prime is rejected
+        let err = read_r1cs_file::<_, Goldilocks>(Cursor::new(r1cs(8, P)))
+            .err()
+            .unwrap();
+        assert!(matches!(err, NovaScotiaError::NonCanonicalFieldElement(_)));
+
+        // and so is the file for a field that is wider than the 8 bytes it declares
+        type Fr = <nova_snark::provider::PallasEngine as Engine>::Scalar;
+        let err = read_r1cs_file::<_, Fr>(Cursor::new(r1cs(8, P - 1)))
+            .err()
+            .unwrap();
+        assert!(matches!(err, NovaScotiaError::FieldMismatch { .. }));
+    }
+
+    #[test]
+    fn test_wide_field_element() {
+        use super::*;
+
+        // a 40-byte element whose high bytes are zero still fits a 32-byte field
+        type Fr = <nova_snark::provider::PallasEngine as Engine>::Scalar;
+        let mut bytes = Fr::from(7).to_repr().as_ref().to_vec();
+        bytes.resize(40, 0);
+        assert_eq!(read_field::<_, Fr>(&bytes[..], 40).unwrap(), Fr::from(7));
+        bytes[39] = 1;
+        assert!(read_field::<_, Fr>(&bytes[..], 40).is_err());
+        assert_eq!(
+            read_field::<_, Goldilocks>(&bytes[..8], 8).unwrap(),
+            Goldilocks::from(7)
+        );
+    }
+
+    #[test]
+    fn test_custom_gates() {
+        use super::*;
+        use crate::circom::prime::modulus;
+        use std::io::Cursor;
+
+        type G1 = nova_snark::provider::PallasEngine;
+        type G2 = nova_snark::provider::VestaEngine;
+        type Fr = <G1 as Engine>::Scalar;
+
+        // four wires, no R1CS constraints, and a `CMul(5)` gate applied to wires 1, 2 and 3
+        let r1cs = |gate_id: u32, with_uses: bool| {
+            let mut header = 32u32.to_le_bytes().to_vec();
+            header.extend(modulus::<Fr>().to_bytes_le());
+            for n in [4u32, 1, 2, 0] {
+                header.extend(n.to_le_bytes());
+            }
+            header.extend(4u64.to_le_bytes());
+            header.extend(0u32.to_le_bytes());
+            let wires = (0..4u64).flat_map(|label| label.to_le_bytes()).collect();
+
+            let mut gates = 1u32.to_le_bytes().to_vec();
+            gates.extend(b"CMul\0");
+            gates.extend(1u32.to_le_bytes());
+            gates.extend(Fr::from(5).to_repr().as_ref());
+
+            let mut uses = 1u32.to_le_bytes().to_vec();
+            uses.extend(gate_id.to_le_bytes());
+            uses.extend(3u32.to_le_bytes());
+            for signal in 1..4u64 {
+                uses.extend(signal.to_le_bytes());
+            }
+
+            let mut sections = vec![(1, header), (2, vec![]), (3, wires), (4, gates)];
+            if with_uses {
+                sections.push((5, uses));
+            }
+            Cursor::new(r1cs_bytes(sections))
+        };
+
+        let file = from_reader::<_, G1, G2>(r1cs(0, true)).unwrap();
+        assert_eq!(
+            file.custom_gates,
+            vec![CustomGate {
+                template_name: "CMul".to_string(),
+                parameters: vec![Fr::from(5)],
+            }]
+        );
+        assert_eq!(
+            file.custom_gate_uses,
+            vec![CustomGateUse {
+                id: 0,
+                signals: vec![1, 2, 3],
+            }]
+        );
+        assert_eq!(
+            file.check_no_custom_gates().err().unwrap().to_string(),
+            "circuit uses custom gates, which Nova Scotia cannot fold: 1 applications of CMul"
+        );
+
+        for malformed in [r1cs(1, true), r1cs(0, false)] {
+            assert!(matches!(
+                from_reader::<_, G1, G2>(malformed),
+                Err(NovaScotiaError::MalformedR1CS(_))
+            ));
+        }
     }
 }
@@ -0,0 +1,509 @@
+// Non-uniform step circuits: several circom circuits folded with SuperNova.
+//
+// Every registered circuit is a separate SuperNova step circuit with its own R1CS shape, so a
+// step only pays for the circuit the program counter selects. The program counter is not part of
+// the state `z`: each circuit outputs it in its `next_pc` signal, and SuperNova checks that the
+// circuit folded next is the one it selects.
+use std::path::Path;
+use std::sync::Arc;
+
+use arecibo::supernova::{NonUniformCircuit, PublicParams};
+use arecibo::traits::circuit_supernova::{StepCircuit, TrivialSecondaryCircuit};
+use arecibo::traits::Engine;
+use bellpepper_core::{num::AllocatedNum, ConstraintSystem, LinearCombination, SynthesisError};
+use ff::PrimeField;
+
+use crate::circom::circuit::{impl_r1cs_synthesizer, synthesize_r1cs, R1CS};
+use crate::circom::reader::load_r1cs_for_field;
+use crate::circom::sym::SymbolTable;
+use crate::error::NovaScotiaError;
+use crate::FileLocation;
+
+/// The step circuit SuperNova folds on the secondary curve.
+pub type SecondaryCircuit<E> = TrivialSecondaryCircuit<<E as Engine>::Scalar>;
+
+/// SuperNova public parameters of a [`NonUniformProgram`].
+pub type NonUniformPublicParams<E1, E2> =
+    PublicParams<E1, E2, ProgramStepCircuit<<E1 as Engine>::Scalar>, SecondaryCircuit<E2>>;
+
+/// One circuit of a non-uniform program, together with its witness generator.
+#[derive(Clone)]
+pub struct ProgramCircuit<Fr: PrimeField> {
+    pub r1cs: R1CS<Fr>,
+    /// witness index of the signal holding the program counter of the next step
+    pub next_pc: usize,
+    pub witness_generator_file: FileLocation,
+}
+
+impl<Fr: PrimeField> ProgramCircuit<Fr> {
+    pub fn new(
+        r1cs: R1CS<Fr>,
+        next_pc: usize,
+        witness_generator_file: FileLocation,
+    ) -> Result<Self, NovaScotiaError> {
+        if next_pc == 0 || next_pc >= r1cs.num_variables {
+            return Err(NovaScotiaError::InvalidInput(format!(
+                "next_pc signal at witness index {} is outside of the circuit's {} variables",
+                next_pc, r1cs.num_variables
+            )));
+        }
+        Ok(ProgramCircuit {
+            r1cs,
+            next_pc,
+            witness_generator_file,
+        })
+    }
+
+    /// locate `main.step_in[*]`, `main.step_out[*]` and `main.next_pc` in a circom symbol file
+    pub fn from_symbols(
+        r1cs: R1CS<Fr>,
+        symbols: &SymbolTable,
+        witness_generator_file: FileLocation,
+    ) -> Result<Self, NovaScotiaError> {
+        let r1cs = r1cs.with_symbols(symbols)?;
+        let next_pc = symbols.array_witnesses("main.next_pc")?;
+        match next_pc[..] {
+            [next_pc] => Self::new(r1cs, next_pc, witness_generator_file),
+            _ => Err(NovaScotiaError::InvalidInput(
+                "main.next_pc not found in symbols".to_string(),
+            )),
+        }
+    }
+
+    /// load a circuit compiled by circom from its `.r1cs` file and the `.sym` file that
+    /// [`Self::from_symbols`] reads
+    pub fn load(
+        r1cs: &FileLocation,
+        symbols: &Path,
+        witness_generator_file: FileLocation,
+    ) -> Result<Self, NovaScotiaError> {
+        Self::from_symbols(
+            load_r1cs_for_field(r1cs)?,
+            &SymbolTable::from_file(symbols)?,
+            witness_generator_file,
+        )
+    }
+}
+
+/// The circuits a non-uniform computation can choose from, indexed by program counter.
+#[derive(Clone)]
+pub struct NonUniformProgram<Fr: PrimeField> {
+    circuits: Arc<Vec<ProgramCircuit<Fr>>>,
+}
+
+impl<Fr: PrimeField> NonUniformProgram<Fr> {
+    pub fn new(circuits: Vec<ProgramCircuit<Fr>>) -> Result<Self, NovaScotiaError> {
+        let arity = match circuits.first() {
+            Some(circuit) => circuit.r1cs.io.arity(),
+            None => {
+                return Err(NovaScotiaError::InvalidInput(
+                    "a non-uniform program needs at least one circuit".to_string(),
+                ))
+            }
+        };
+        if let Some((pc, circuit)) = circuits
+            .iter()
+            .enumerate()
+            .find(|(_, circuit)| circuit.r1cs.io.arity() != arity)
+        {
+            return Err(NovaScotiaError::InvalidInput(format!(
+                "arity mismatch: circuit 0 has {} step_in signals but circuit {} has {}",
+                arity,
+                pc,
+                circuit.r1cs.io.arity()
+            )));
+        }
+        Ok(NonUniformProgram {
+            circuits: Arc::new(circuits),
+        })
+    }
+
+    pub fn circuits(&self) -> &[ProgramCircuit<Fr>] {
+        &self.circuits
+    }
+
+    /// number of `step_in` signals shared by all circuits
+    pub fn arity(&self) -> usize {
+        self.circuits[0].r1cs.io.arity()
+    }
+
+    /// index of the circuit a program counter value selects, if any
+    pub fn resolve_pc(&self, pc: Fr) -> Option<usize> {
+        (0..self.circuits.len()).find(|&i| Fr::from(i as u64) == pc)
+    }
+
+    /// the step circuit running the circuit at `pc` on `witness`
+    pub fn step(&self, pc: usize, witness: Option<Vec<Fr>>) -> ProgramStepCircuit<Fr> {
+        ProgramStepCircuit {
+            program: self.clone(),
+            pc,
+            witness,
+        }
+    }
+}
+
+/// SuperNova step circuit that runs the program circuit selected by `pc` on `witness`.
+///
+/// Its state is the `step_in` of the selected circuit, and it outputs that circuit's `step_out`
+/// together with its `next_pc` as the program counter of the following step.
+#[derive(Clone)]
+pub struct ProgramStepCircuit<Fr: PrimeField> {
+    pub program: NonUniformProgram<Fr>,
+    pub pc: usize,
+    pub witness: Option<Vec<Fr>>,
+}
+
+impl<Fr: PrimeField> ProgramStepCircuit<Fr> {
+    fn value(&self, i: usize) -> Fr {
+        match &self.witness {
+            None => Fr::ZERO,
+            Some(w) => w[i],
+        }
+    }
+
+    /// `step_out` of the selected circuit
+    pub fn get_public_outputs(&self) -> Vec<Fr> {
+        let circuit = &self.program.circuits[self.pc];
+        circuit
+            .r1cs
+            .io
+            .step_out
+            .iter()
+            .map(|&i| self.value(i))
+            .collect()
+    }
+
+    /// `next_pc` of the selected circuit
+    pub fn next_pc(&self) -> Fr {
+        self.value(self.program.circuits[self.pc].next_pc)
+    }
+}
+
+impl<Fr: PrimeField> StepCircuit<Fr> for ProgramStepCircuit<Fr> {
+    fn arity(&self) -> usize {
+        self.program.arity()
+    }
+
+    fn circuit_index(&self) -> usize {
+        self.pc
+    }
+
+    fn synthesize<CS: ConstraintSystem<Fr>>(
+        &self,
+        cs: &mut CS,
+        _pc: Option<&AllocatedNum<Fr>>,
+        z: &[AllocatedNum<Fr>],
+    ) -> Result<(Option<AllocatedNum<Fr>>, Vec<AllocatedNum<Fr>>), SynthesisError> {
+        let circuit = &self.program.circuits[self.pc];
+        let r1cs = &circuit.r1cs;
+        if z.len() != r1cs.io.arity() {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+
+        let vars = synthesize_r1cs(&mut BellpepperSynthesizer(cs), r1cs, |i| self.value(i), z)?;
+
+        // SuperNova itself checks that `pc` selects this circuit
+        let next_pc = vars[circuit.next_pc - 1].clone();
+        let z_out = r1cs.io.step_out.iter().map(|&w| vars[w - 1].clone());
+        Ok((Some(next_pc), z_out.collect()))
+    }
+}
+
+impl_r1cs_synthesizer!(
+    BellpepperSynthesizer,
+    ConstraintSystem,
+    AllocatedNum,
+    LinearCombination,
+    SynthesisError
+);
+
+/// A [`NonUniformProgram`] whose first step runs the circuit at `start_pc`, as arecibo sees it.
+pub(crate) struct ProgramRun<'a, Fr: PrimeField> {
+    pub(crate) program: &'a NonUniformProgram<Fr>,
+    pub(crate) start_pc: usize,
+}
+
+impl<E1, E2> NonUniformCircuit<E1, E2, ProgramStepCircuit<E1::Scalar>, SecondaryCircuit<E2>>
+    for ProgramRun<'_, E1::Scalar>
+where
+    E1: Engine<Base = <E2 as Engine>::Scalar>,
+    E2: Engine<Base = <E1 as Engine>::Scalar>,
+{
+    fn initial_circuit_index(&self) -> usize {
+        self.start_pc
+    }
+
+    fn num_circuits(&self) -> usize {
+        self.program.circuits.len()
+    }
+
+    fn primary_circuit(&self, circuit_index: usize) -> ProgramStepCircuit<E1::Scalar> {
+        self.program.step(circuit_index, None)
+    }
+
+    fn secondary_circuit(&self) -> SecondaryCircuit<E2> {
+        SecondaryCircuit::<E2>::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circom::circuit::IoLayout;
+    use bellpepper_core::test_cs::TestConstraintSystem;
+    use ff::Field;
+    use std::path::PathBuf;
+
+    type E1 = arecibo::provider::PallasEngine;
+    type E2 = arecibo::provider::VestaEngine;
+    type Fr = <E1 as Engine>::Scalar;
+
+    // wires: 1 step_out[0], 2 next_pc, 3 step_in[0]
+    fn program(
+        double_generator: FileLocation,
+        increment_generator: FileLocation,
+    ) -> NonUniformProgram<Fr> {
+        let io = IoLayout {
+            step_in: vec![3],
+            step_out: vec![1],
+        };
+        // step_out[0] <== 2 * step_in[0]; next_pc <== 1
+        let double = R1CS {
+            num_inputs: 4,
+            num_outputs: 2,
+            num_aux: 0,
+            num_variables: 4,
+            constraints: vec![
+                (
+                    vec![(3, Fr::from(2))],
+                    vec![(0, Fr::ONE)],
+                    vec![(1, Fr::ONE)],
+                ),
+                (vec![(0, Fr::ONE)], vec![(0, Fr::ONE)], vec![(2, Fr::ONE)]),
+            ],
+            io: io.clone(),
+        };
+        // step_out[0] <== step_in[0] + 1; next_pc is any bit
+        let increment = R1CS {
+            num_inputs: 4,
+            num_outputs: 2,
+            num_aux: 0,
+            num_variables: 4,
+            constraints: vec![
+                (
+                    vec![(3, Fr::ONE), (0, Fr::ONE)],
+                    vec![(0, Fr::ONE)],
+                    vec![(1, Fr::ONE)],
+                ),
+                (vec![(2, Fr::ONE)], vec![(2, Fr::ONE)], vec![(2, Fr::ONE)]),
+            ],
+            io,
+        };
+        NonUniformProgram::new(vec![
+            ProgramCircuit::new(double, 2, double_generator).unwrap(),
+            ProgramCircuit::new(increment, 2, increment_generator).unwrap(),
+        ])
+        .unwrap()
+    }
+
+    fn unused_generator() -> FileLocation {
+        FileLocation::PathBuf(PathBuf::from("unused"))
+    }
+
+    fn synthesize(circuit: &ProgramStepCircuit<Fr>, z: &[u64]) -> (bool, Fr, Vec<Fr>) {
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        let z = z
+            .iter()
+            .enumerate()
+            .map(|(i, &v)| {
+                AllocatedNum::alloc(cs.namespace(|| format!("z{}", i)), || Ok(Fr::from(v))).unwrap()
+            })
+            .collect::<Vec<_>>();
+        let (next_pc, z_out) = circuit.synthesize(&mut cs, None, &z).unwrap();
+        (
+            cs.is_satisfied(),
+            next_pc.unwrap().get_value().unwrap(),
+            z_out.iter().map(|v| v.get_value().unwrap()).collect(),
+        )
+    }
+
+    #[test]
+    fn test_program_step_circuit() {
+        let program = program(unused_generator(), unused_generator());
+        let circuit = program.step(0, Some(vec![Fr::ONE, Fr::from(6), Fr::ONE, Fr::from(3)]));
+        assert_eq!(circuit.arity(), 1);
+        assert_eq!(circuit.circuit_index(), 0);
+        assert_eq!(circuit.next_pc(), Fr::ONE);
+        assert_eq!(circuit.get_public_outputs(), vec![Fr::from(6)]);
+        assert_eq!(
+            synthesize(&circuit, &[3]),
+            (true, Fr::ONE, vec![Fr::from(6)])
+        );
+
+        let circuit = program.step(1, Some(vec![Fr::ONE, Fr::from(7), Fr::ZERO, Fr::from(6)]));
+        assert_eq!(circuit.circuit_index(), 1);
+        assert_eq!(
+            synthesize(&circuit, &[6]),
+            (true, Fr::ZERO, vec![Fr::from(7)])
+        );
+        // `step_in` is bound to the state
+        assert!(!synthesize(&circuit, &[5]).0);
+
+        // the witness of the incrementing circuit does not satisfy the doubling one
+        let circuit = program.step(0, circuit.witness);
+        assert!(!synthesize(&circuit, &[6]).0);
+    }
+
+    #[test]
+    fn test_program_validation() {
+        let program = program(unused_generator(), unused_generator());
+        assert_eq!(program.resolve_pc(Fr::ONE), Some(1));
+        assert_eq!(program.resolve_pc(Fr::from(2)), None);
+
+        let mut circuits = program.circuits().to_vec();
+        circuits[1].r1cs.io = IoLayout::symmetric(5);
+        let err = NonUniformProgram::new(circuits).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "invalid input: arity mismatch: circuit 0 has 1 step_in signals but circuit 1 has 2"
+        );
+
+        let r1cs = program.circuits()[0].r1cs.clone();
+        assert!(ProgramCircuit::new(r1cs, 4, unused_generator()).is_err());
+    }
+
+    #[test]
+    fn test_nonuniform_compiled_circuits() {
+        use crate::{
+            create_nonuniform_public_params, create_nonuniform_recursive_circuit,
+            verify_nonuniform_recursive_circuit,
+        };
+        use serde_json::json;
+        use std::collections::HashMap;
+
+        // the toy circuit compiled for the Pasta curves, with step_out[0] <== step_in[0] + adder
+        // and step_out[1] <== step_in[0] + step_in[1]; it has no next_pc signal, so both circuits
+        // of the program are the toy circuit selecting the next one with step_out[0]
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/toy/pasta");
+        let r1cs =
+            load_r1cs_for_field::<Fr>(&FileLocation::PathBuf(root.join("toy.r1cs"))).unwrap();
+        let next_pc = r1cs.io.step_out[0];
+        let wasm = FileLocation::PathBuf(root.join("toy_js/toy.wasm"));
+        let circuit = ProgramCircuit::new(r1cs, next_pc, wasm).unwrap();
+        let program = NonUniformProgram::new(vec![circuit.clone(), circuit]).unwrap();
+
+        let pp = create_nonuniform_public_params::<E1, E2>(&program);
+        // (0, 10) -> (1, 10) -> (0, 11) -> (1, 11), running circuits 0, 1 and 0
+        let private_inputs = ["1", "-1", "1"]
+            .iter()
+            .map(|adder| HashMap::from([("adder".to_string(), json!(adder))]))
+            .collect();
+        let z0 = vec![Fr::ZERO, Fr::from(10)];
+        let output =
+            create_nonuniform_recursive_circuit(&program, private_inputs, 0, z0.clone(), &pp)
+                .unwrap();
+        assert_eq!(output.num_steps, 3);
+        assert_eq!(output.zn, vec![Fr::ONE, Fr::from(11)]);
+        assert_eq!(output.pc, Fr::ONE);
+        assert_eq!(
+            verify_nonuniform_recursive_circuit(&output.recursive_snark, &pp, &z0).unwrap(),
+            output.zn
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_nonuniform_recursive_circuit() {
+        use crate::circom::prime::modulus;
+        use crate::{
+            create_nonuniform_public_params, create_nonuniform_recursive_circuit,
+            verify_nonuniform_recursive_circuit,
+        };
+        use std::collections::HashMap;
+        use std::fs;
+        use std::os::unix::fs::PermissionsExt;
+
+        fn wtns_bytes(witness: &[u64]) -> Vec<u8> {
+            let mut buf = b"wtns".to_vec();
+            for word in [2u32, 2, 1] {
+                buf.extend(word.to_le_bytes());
+            }
+            buf.extend(40u64.to_le_bytes());
+            buf.extend(32u32.to_le_bytes());
+            buf.extend(modulus::<Fr>().to_bytes_le());
+            buf.extend((witness.len() as u32).to_le_bytes());
+            buf.extend(2u32.to_le_bytes());
+            buf.extend((witness.len() as u64 * 32).to_le_bytes());
+            for w in witness {
+                buf.extend(Fr::from(*w).to_repr().as_ref());
+            }
+            buf
+        }
+
+        // fake witness generators that answer with a witness chosen by `step_in`
+        let dir = tempfile::tempdir().unwrap();
+        let generator = |name: &str, witnesses: &[[u64; 4]]| {
+            for w in witnesses {
+                let fixture = dir.path().join(format!("{}_{}.wtns", name, w[3]));
+                fs::write(fixture, wtns_bytes(w)).unwrap();
+            }
+            let path = dir.path().join(format!("{}.sh", name));
+            fs::write(
+                &path,
+                format!(
+                    "#!/bin/sh\nn=$(sed -e 's/.*\"step_in\":\\[\"\\([0-9]*\\)\".*/\\1/' \"$1\")\ncp {}/{}_$n.wtns \"$2\"\n",
+                    dir.path().display(),
+                    name
+                ),
+            )
+            .unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+            FileLocation::PathBuf(path)
+        };
+        // 3 -double-> 6 -increment-> 7 -double-> 14, the increment step chooses pc 0
+        let program = program(
+            generator("double", &[[1, 6, 1, 3], [1, 14, 1, 7]]),
+            generator("increment", &[[1, 7, 0, 6]]),
+        );
+
+        let pp = create_nonuniform_public_params::<E1, E2>(&program);
+        let output = create_nonuniform_recursive_circuit(
+            &program,
+            vec![HashMap::new(); 3],
+            0,
+            vec![Fr::from(3)],
+            &pp,
+        )
+        .unwrap();
+        assert_eq!(output.num_steps, 3);
+        assert_eq!(output.zn, vec![Fr::from(14)]);
+        assert_eq!(output.pc, Fr::ONE);
+
+        let z_n = verify_nonuniform_recursive_circuit(&output.recursive_snark, &pp, &[Fr::from(3)])
+            .unwrap();
+        assert_eq!(z_n, output.zn);
+        assert!(
+            verify_nonuniform_recursive_circuit(&output.recursive_snark, &pp, &[Fr::from(4)])
+                .is_err()
+        );
+
+        // the last step selected pc 1, but no witness for 14 was prepared
+        let res = create_nonuniform_recursive_circuit(
+            &program,
+            vec![HashMap::new(); 4],
+            0,
+            vec![Fr::from(3)],
+            &pp,
+        );
+        assert!(matches!(res, Err(NovaScotiaError::WitnessGenerator { .. })));
+
+        let res = create_nonuniform_recursive_circuit(
+            &program,
+            vec![HashMap::new()],
+            2,
+            vec![Fr::from(3)],
+            &pp,
+        );
+        assert!(matches!(res, Err(NovaScotiaError::InvalidInput(_))));
+    }
+}
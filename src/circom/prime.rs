@@ -0,0 +1,176 @@
+use std::fmt;
+
+use crate::error::NovaScotiaError;
+use ff::PrimeField;
+use nova_snark::provider::{
+    Bn256EngineIPA, GrumpkinEngine, PallasEngine, Secp256k1Engine, Secq256k1Engine, VestaEngine,
+};
+use nova_snark::traits::Engine;
+use num_bigint::BigUint;
+use num_traits::Num;
+
+/// Nova engines whose scalar field Circom can compile circuits for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SupportedEngine {
+    Pallas,
+    Vesta,
+    Bn256,
+    Grumpkin,
+    Secp256k1,
+    Secq256k1,
+}
+
+impl SupportedEngine {
+    pub const ALL: [SupportedEngine; 6] = [
+        SupportedEngine::Pallas,
+        SupportedEngine::Vesta,
+        SupportedEngine::Bn256,
+        SupportedEngine::Grumpkin,
+        SupportedEngine::Secp256k1,
+        SupportedEngine::Secq256k1,
+    ];
+
+    /// name of the Nova engine, as found in `nova_snark::provider`
+    pub fn engine_name(&self) -> &'static str {
+        match self {
+            SupportedEngine::Pallas => "PallasEngine",
+            SupportedEngine::Vesta => "VestaEngine",
+            SupportedEngine::Bn256 => "Bn256EngineKZG/Bn256EngineIPA",
+            SupportedEngine::Grumpkin => "GrumpkinEngine",
+            SupportedEngine::Secp256k1 => "Secp256k1Engine",
+            SupportedEngine::Secq256k1 => "Secq256k1Engine",
+        }
+    }
+
+    /// value of circom's `--prime` flag that compiles for this engine's scalar field, if any
+    pub fn circom_prime(&self) -> Option<&'static str> {
+        match self {
+            SupportedEngine::Pallas => Some("vesta"),
+            SupportedEngine::Vesta => Some("pallas"),
+            SupportedEngine::Bn256 => Some("bn128"),
+            SupportedEngine::Grumpkin => Some("grumpkin"),
+            SupportedEngine::Secp256k1 | SupportedEngine::Secq256k1 => None,
+        }
+    }
+
+    /// modulus of the engine's scalar field
+    pub fn modulus(&self) -> BigUint {
+        match self {
+            SupportedEngine::Pallas => modulus::<<PallasEngine as Engine>::Scalar>(),
+            SupportedEngine::Vesta => modulus::<<VestaEngine as Engine>::Scalar>(),
+            SupportedEngine::Bn256 => modulus::<<Bn256EngineIPA as Engine>::Scalar>(),
+            SupportedEngine::Grumpkin => modulus::<<GrumpkinEngine as Engine>::Scalar>(),
+            SupportedEngine::Secp256k1 => modulus::<<Secp256k1Engine as Engine>::Scalar>(),
+            SupportedEngine::Secq256k1 => modulus::<<Secq256k1Engine as Engine>::Scalar>(),
+        }
+    }
+
+    /// stable identifier of the engine in Nova Scotia file formats
+    pub fn id(&self) -> u8 {
+        match self {
+            SupportedEngine::Pallas => 1,
+            SupportedEngine::Vesta => 2,
+            SupportedEngine::Bn256 => 3,
+            SupportedEngine::Grumpkin => 4,
+            SupportedEngine::Secp256k1 => 5,
+            SupportedEngine::Secq256k1 => 6,
+        }
+    }
+
+    /// the engine with the given [`SupportedEngine::id`]
+    pub fn from_id(id: u8) -> Option<SupportedEngine> {
+        SupportedEngine::ALL
+            .into_iter()
+            .find(|engine| engine.id() == id)
+    }
+
+    /// the supported engine whose scalar field is `Fr`
+    pub fn of<Fr: PrimeField>() -> Option<SupportedEngine> {
+        Self::from_modulus(&modulus::<Fr>())
+    }
+
+    /// the supported engine whose scalar field has the given modulus
+    pub fn from_modulus(prime: &BigUint) -> Option<SupportedEngine> {
+        SupportedEngine::ALL
+            .into_iter()
+            .find(|engine| &engine.modulus() == prime)
+    }
+}
+
+impl fmt::Display for SupportedEngine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.circom_prime() {
+            Some(prime) => write!(f, "{} (circom --prime {})", self.engine_name(), prime),
+            None => write!(f, "{}", self.engine_name()),
+        }
+    }
+}
+
+/// modulus of a prime field
+pub fn modulus<Fr: PrimeField>() -> BigUint {
+    let hex = Fr::MODULUS.trim_start_matches("0x");
+    BigUint::from_str_radix(hex, 16).expect("PrimeField::MODULUS is a hex string")
+}
+
+fn describe(prime: &BigUint) -> String {
+    match SupportedEngine::from_modulus(prime) {
+        Some(engine) => format!("{} scalar field", engine),
+        None => format!("unknown field with modulus 0x{}", prime.to_str_radix(16)),
+    }
+}
+
+/// check that a little-endian prime read from a circom file is the modulus of `Fr`
+pub(crate) fn check_prime<Fr: PrimeField>(prime_le: &[u8]) -> Result<(), NovaScotiaError> {
+    let found = BigUint::from_bytes_le(prime_le);
+    let expected = modulus::<Fr>();
+    if found != expected {
+        return Err(NovaScotiaError::FieldMismatch {
+            expected: describe(&expected),
+            found: describe(&found),
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_engine_moduli() {
+        assert_eq!(
+            SupportedEngine::Pallas.modulus().to_str_radix(16),
+            "40000000000000000000000000000000224698fc0994a8dd8c46eb2100000001"
+        );
+        assert_eq!(
+            SupportedEngine::Bn256.modulus().to_str_radix(10),
+            "21888242871839275222246405745257275088548364400416034343698204186575808495617"
+        );
+        for engine in SupportedEngine::ALL {
+            assert_eq!(
+                SupportedEngine::from_modulus(&engine.modulus()),
+                Some(engine)
+            );
+            assert_eq!(SupportedEngine::from_id(engine.id()), Some(engine));
+        }
+    }
+
+    #[test]
+    fn test_check_prime() {
+        type Fr = <PallasEngine as Engine>::Scalar;
+        let prime = modulus::<Fr>().to_bytes_le();
+        assert!(check_prime::<Fr>(&prime).is_ok());
+
+        let bn_prime = SupportedEngine::Bn256.modulus().to_bytes_le();
+        match check_prime::<Fr>(&bn_prime) {
+            Err(NovaScotiaError::FieldMismatch { expected, found }) => {
+                assert_eq!(expected, "PallasEngine (circom --prime vesta) scalar field");
+                assert_eq!(
+                    found,
+                    "Bn256EngineKZG/Bn256EngineIPA (circom --prime bn128) scalar field"
+                );
+            }
+            res => panic!("unexpected result {:?}", res),
+        }
+    }
+}
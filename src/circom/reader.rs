@@ -1,155 +1,666 @@
-use anyhow::bail;
 use byteorder::{LittleEndian, ReadBytesExt};
-use std::env::current_dir;
+use std::collections::BTreeMap;
 use std::fs;
 use std::fs::{File, OpenOptions};
-use std::io::{BufReader, Read, Seek};
+use std::io::{BufReader, Read};
 use std::path::Path;
 use std::process::Command;
-use std::str;
 
-use crate::circom::circuit::R1CS;
-use crate::circom::file::{from_reader, read_field};
+use crate::circom::circuit::{CircuitJson, IoLayout, WireNames, R1CS};
+use crate::circom::file::{from_bytes, from_reader, header_from_reader, read_field};
+use crate::circom::prime::{check_prime, SupportedEngine};
+use crate::circom::sym::SymbolTable;
+use crate::convert::from_decimal;
+use crate::error::NovaScotiaError;
 use crate::FileLocation;
 use ff::PrimeField;
+use memmap2::MmapOptions;
 use nova_snark::traits::Engine;
+use num_bigint::BigUint;
+use num_traits::Num;
 
+/// run a circom C++ witness generator binary on a JSON input.
+///
+/// The input and output files live in a private temporary directory that is removed when this
+/// function returns, so concurrent invocations never share files.
 pub fn generate_witness_from_bin<Fr: PrimeField>(
     witness_bin: &Path,
-    witness_input_json: &String,
-    witness_output: &Path,
-) -> Vec<Fr> {
-    let root = current_dir().unwrap();
-    let witness_generator_input = root.join("circom_input.json");
-    fs::write(&witness_generator_input, witness_input_json).unwrap();
+    witness_input_json: &str,
+) -> Result<Vec<Fr>, NovaScotiaError> {
+    let dir = tempfile::Builder::new()
+        .prefix("nova-scotia-witness")
+        .tempdir()?;
+    let witness_generator_input = dir.path().join("input.json");
+    let witness_output = dir.path().join("witness.wtns");
+    fs::write(&witness_generator_input, witness_input_json)?;
 
     let output = Command::new(witness_bin)
         .arg(&witness_generator_input)
-        .arg(witness_output)
-        .output()
-        .expect("failed to execute process");
-    if output.stdout.len() > 0 || output.stderr.len() > 0 {
-        print!("stdout: {}", str::from_utf8(&output.stdout).unwrap());
-        print!("stderr: {}", str::from_utf8(&output.stderr).unwrap());
+        .arg(&witness_output)
+        .output()?;
+    if !output.status.success() {
+        return Err(NovaScotiaError::WitnessGenerator {
+            status: output.status.code(),
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        });
     }
-    let _ = fs::remove_file(witness_generator_input);
-    load_witness_from_file(witness_output)
+    load_witness_from_file(&witness_output)
 }
 
-/// load witness file by filename (binary format only).
-pub fn load_witness_from_file<Fr: PrimeField>(filename: &Path) -> Vec<Fr> {
-    load_witness_from_bin_file::<Fr>(filename)
+/// load witness file by filename, either a `.wtns` file or the JSON array of decimal strings
+/// written by `snarkjs wtns export json`
+pub fn load_witness_from_file<Fr: PrimeField>(filename: &Path) -> Result<Vec<Fr>, NovaScotiaError> {
+    if is_json_file(filename, b"wtns")? {
+        load_witness_from_json_file::<Fr>(filename)
+    } else {
+        load_witness_from_bin_file::<Fr>(filename)
+    }
+}
+
+/// load witness from json file by filename
+pub fn load_witness_from_json_file<Fr: PrimeField>(
+    filename: &Path,
+) -> Result<Vec<Fr>, NovaScotiaError> {
+    let reader = OpenOptions::new().read(true).open(filename)?;
+    load_witness_from_json_reader::<Fr, _>(BufReader::new(reader))
+}
+
+/// load witness from a JSON array of decimal strings by a reader
+pub fn load_witness_from_json_reader<Fr: PrimeField, R: Read>(
+    reader: R,
+) -> Result<Vec<Fr>, NovaScotiaError> {
+    let values: Vec<String> = serde_json::from_reader(reader)?;
+    values
+        .iter()
+        .map(|value| from_decimal::<Fr>(value))
+        .collect()
+}
+
+/// whether a file is JSON rather than the binary format starting with `magic`: binary files are
+/// recognised by their magic, anything else is JSON if it is named `.json` or starts like JSON
+fn is_json_file(filename: &Path, magic: &[u8; 4]) -> Result<bool, NovaScotiaError> {
+    let mut start = Vec::with_capacity(64);
+    File::open(filename)?.take(64).read_to_end(&mut start)?;
+    if start.starts_with(magic) {
+        return Ok(false);
+    }
+    let json_extension = filename
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("json"));
+    let json_start = matches!(
+        start.iter().find(|byte| !byte.is_ascii_whitespace()),
+        Some(b'{' | b'[')
+    );
+    Ok(json_extension || json_start)
 }
 
 /// load witness from bin file by filename
-pub fn load_witness_from_bin_file<Fr: PrimeField>(filename: &Path) -> Vec<Fr> {
-    let reader = OpenOptions::new()
-        .read(true)
-        .open(filename)
-        .expect("unable to open.");
+pub fn load_witness_from_bin_file<Fr: PrimeField>(
+    filename: &Path,
+) -> Result<Vec<Fr>, NovaScotiaError> {
+    let reader = OpenOptions::new().read(true).open(filename)?;
     load_witness_from_bin_reader::<Fr, BufReader<File>>(BufReader::new(reader))
-        .expect("read witness failed")
 }
 
 /// load witness from u8 array
-pub fn load_witness_from_array<Fr: PrimeField>(buffer: Vec<u8>) -> Result<Vec<Fr>, anyhow::Error> {
+pub fn load_witness_from_array<Fr: PrimeField>(
+    buffer: Vec<u8>,
+) -> Result<Vec<Fr>, NovaScotiaError> {
     load_witness_from_bin_reader::<Fr, _>(buffer.as_slice())
 }
 
+fn malformed(reason: &str) -> NovaScotiaError {
+    NovaScotiaError::MalformedWitness(reason.to_string())
+}
+
 /// load witness from u8 array by a reader
 pub(crate) fn load_witness_from_bin_reader<Fr: PrimeField, R: Read>(
     mut reader: R,
-) -> Result<Vec<Fr>, anyhow::Error> {
+) -> Result<Vec<Fr>, NovaScotiaError> {
     let mut wtns_header = [0u8; 4];
     reader.read_exact(&mut wtns_header)?;
     if wtns_header != [119, 116, 110, 115] {
         // ruby -e 'p "wtns".bytes' => [119, 116, 110, 115]
-        bail!("invalid file header");
+        return Err(malformed("invalid file header"));
     }
     let version = reader.read_u32::<LittleEndian>()?;
-    // println!("wtns version {}", version);
     if version > 2 {
-        bail!("unsupported file version");
+        return Err(malformed("unsupported file version"));
     }
     let num_sections = reader.read_u32::<LittleEndian>()?;
     if num_sections != 2 {
-        bail!("invalid num sections");
+        return Err(malformed("invalid num sections"));
     }
     // read the first section
     let sec_type = reader.read_u32::<LittleEndian>()?;
     if sec_type != 1 {
-        bail!("invalid section type");
+        return Err(malformed("invalid section type"));
     }
     let sec_size = reader.read_u64::<LittleEndian>()?;
-    if sec_size != 4 + 32 + 4 {
-        bail!("invalid section len")
-    }
     let field_size = reader.read_u32::<LittleEndian>()?;
-    if field_size != 32 {
-        bail!("invalid field byte size");
-    }
-    let mut prime = vec![0u8; field_size as usize];
-    reader.read_exact(&mut prime)?;
-    // if prime != hex!("010000f093f5e1439170b97948e833285d588181b64550b829a031e1724e6430") {
-    //     bail!("invalid curve prime {:?}", prime);
-    // }
+    if field_size == 0 {
+        return Err(malformed("invalid field byte size"));
+    }
+    if sec_size != 4 + field_size as u64 + 4 {
+        return Err(malformed("invalid section len"));
+    }
+    // the sizes are not checked against the file, so only allocate what is actually read
+    let mut prime = vec![];
+    (&mut reader)
+        .take(field_size as u64)
+        .read_to_end(&mut prime)?;
+    if prime.len() != field_size as usize {
+        return Err(malformed("truncated prime"));
+    }
+    check_prime::<Fr>(&prime)?;
     let witness_len = reader.read_u32::<LittleEndian>()?;
-    // println!("witness len {}", witness_len);
     let sec_type = reader.read_u32::<LittleEndian>()?;
     if sec_type != 2 {
-        bail!("invalid section type");
+        return Err(malformed("invalid section type"));
     }
     let sec_size = reader.read_u64::<LittleEndian>()?;
-    if sec_size != (witness_len * field_size) as u64 {
-        bail!("invalid witness section size {}", sec_size);
+    if sec_size != witness_len as u64 * field_size as u64 {
+        return Err(NovaScotiaError::MalformedWitness(format!(
+            "invalid witness section size {}",
+            sec_size
+        )));
     }
-    let mut result = Vec::with_capacity(witness_len as usize);
+    let mut result = vec![];
     for _ in 0..witness_len {
-        result.push(read_field::<&mut R, Fr>(&mut reader)?);
+        result.push(
+            read_field::<&mut R, Fr>(&mut reader, field_size as usize).map_err(
+                |err| match err {
+                    NovaScotiaError::Io(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => {
+                        malformed("truncated witness")
+                    }
+                    err => err,
+                },
+            )?,
+        );
     }
     Ok(result)
 }
 
-/// load r1cs file by filename (binary format only)
-pub fn load_r1cs<G1, G2>(filename: &FileLocation) -> R1CS<<G1 as Engine>::Scalar>
+/// load r1cs file by filename, either a binary `.r1cs` file or the JSON written by
+/// `snarkjs r1cs export json`
+pub fn load_r1cs<G1, G2>(
+    filename: &FileLocation,
+) -> Result<R1CS<<G1 as Engine>::Scalar>, NovaScotiaError>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+{
+    load_r1cs_for_field(filename)
+}
+
+/// load r1cs file by filename, locating `step_in` and `step_out` with the circuit's `.sym` file
+pub fn load_r1cs_with_symbols<G1, G2>(
+    filename: &FileLocation,
+    symbols: &Path,
+) -> Result<R1CS<<G1 as Engine>::Scalar>, NovaScotiaError>
 where
     G1: Engine<Base = <G2 as Engine>::Scalar>,
     G2: Engine<Base = <G1 as Engine>::Scalar>,
 {
+    load_r1cs_for_field_with_symbols(filename, symbols)
+}
+
+/// [`load_r1cs`] for any field `Fr`, not only the scalar fields of `nova-snark`'s engines, e.g.
+/// those of arecibo that non-uniform programs are folded with
+pub fn load_r1cs_for_field<Fr: PrimeField>(
+    filename: &FileLocation,
+) -> Result<R1CS<Fr>, NovaScotiaError> {
     let filename = match filename {
         FileLocation::PathBuf(filename) => filename,
-        FileLocation::URL(_) => panic!("URL-based R1CS loading is not supported without WASM"),
+        FileLocation::URL(url) => {
+            return Err(NovaScotiaError::InvalidInput(format!(
+                "URL-based R1CS loading is not supported: {}",
+                url
+            )))
+        }
     };
-    load_r1cs_from_bin_file::<G1, G2>(filename)
+    if is_json_file(filename, b"r1cs")? {
+        load_r1cs_from_json_file(filename)
+    } else {
+        load_r1cs_from_bin_file(filename)
+    }
 }
 
-/// load r1cs from bin file by filename
-fn load_r1cs_from_bin_file<G1, G2>(filename: &Path) -> R1CS<<G1 as Engine>::Scalar>
+/// [`load_r1cs_with_symbols`] for any field `Fr`
+pub fn load_r1cs_for_field_with_symbols<Fr: PrimeField>(
+    filename: &FileLocation,
+    symbols: &Path,
+) -> Result<R1CS<Fr>, NovaScotiaError> {
+    load_r1cs_for_field(filename)?.with_symbols(&SymbolTable::from_file(symbols)?)
+}
+
+/// name the wires of an R1CS file with the circuit's `.sym` file, for
+/// [`crate::circom::circuit::CircomCircuit::check_witness`]
+pub fn load_wire_names<G1, G2>(
+    filename: &Path,
+    symbols: &Path,
+) -> Result<WireNames, NovaScotiaError>
 where
     G1: Engine<Base = <G2 as Engine>::Scalar>,
     G2: Engine<Base = <G1 as Engine>::Scalar>,
 {
-    let reader = OpenOptions::new()
-        .read(true)
-        .open(filename)
-        .expect("unable to open.");
-    load_r1cs_from_bin::<_, G1, G2>(BufReader::new(reader))
+    let reader = OpenOptions::new().read(true).open(filename)?;
+    let file = from_reader::<_, G1, G2>(BufReader::new(reader))?;
+    Ok(WireNames::new(
+        &SymbolTable::from_file(symbols)?,
+        &file.wire_mapping,
+    ))
 }
 
-/// load r1cs from bin by a reader
-pub(crate) fn load_r1cs_from_bin<R: Read + Seek, G1, G2>(reader: R) -> R1CS<<G1 as Engine>::Scalar>
+/// report which supported Nova engine's scalar field an R1CS file was compiled for
+pub fn engine_for_r1cs(filename: &Path) -> Result<Option<SupportedEngine>, NovaScotiaError> {
+    let reader = OpenOptions::new().read(true).open(filename)?;
+    let header = header_from_reader(BufReader::new(reader))?;
+    Ok(SupportedEngine::from_modulus(&BigUint::from_bytes_le(
+        &header.prime_size,
+    )))
+}
+
+/// load r1cs from bin file by filename
+fn load_r1cs_from_bin_file<Fr: PrimeField>(filename: &Path) -> Result<R1CS<Fr>, NovaScotiaError> {
+    let file = File::open(filename)?;
+    // SAFETY: the mapping is only read while parsing, and like any other reader we rely on the
+    // R1CS file not being truncated or rewritten underneath us
+    let bytes = unsafe { MmapOptions::new().populate().map(&file)? };
+    from_bytes(&bytes)?.into_r1cs()
+}
+
+/// load r1cs from json file by filename
+fn load_r1cs_from_json_file<Fr: PrimeField>(filename: &Path) -> Result<R1CS<Fr>, NovaScotiaError> {
+    let reader = OpenOptions::new().read(true).open(filename)?;
+    let circuit: CircuitJson = serde_json::from_reader(BufReader::new(reader))?;
+    r1cs_from_json(circuit)
+}
+
+/// load r1cs from the JSON written by `snarkjs r1cs export json` by a reader
+pub fn load_r1cs_from_json<R: Read, G1, G2>(
+    reader: R,
+) -> Result<R1CS<<G1 as Engine>::Scalar>, NovaScotiaError>
 where
     G1: Engine<Base = <G2 as Engine>::Scalar>,
     G2: Engine<Base = <G1 as Engine>::Scalar>,
 {
-    let file = from_reader::<_, G1, G2>(reader).expect("unable to read.");
-    let num_inputs = (1 + file.header.n_pub_in + file.header.n_pub_out) as usize;
-    let num_variables = file.header.n_wires as usize;
-    let num_aux = num_variables - num_inputs;
-    R1CS {
+    let circuit: CircuitJson = serde_json::from_reader(reader)?;
+    r1cs_from_json(circuit)
+}
+
+fn r1cs_from_json<Fr: PrimeField>(circuit: CircuitJson) -> Result<R1CS<Fr>, NovaScotiaError> {
+    let malformed = |reason: String| NovaScotiaError::MalformedR1CS(reason);
+    if let Some(prime) = &circuit.prime {
+        let prime = BigUint::from_str_radix(prime, 10)
+            .map_err(|_| malformed(format!("invalid prime {:?}", prime)))?;
+        check_prime::<Fr>(&prime.to_bytes_le())?;
+    }
+    if circuit.use_custom_gates {
+        return Err(NovaScotiaError::UnsupportedCustomGates(
+            "the JSON export sets useCustomGates".to_string(),
+        ));
+    }
+    let num_inputs = circuit
+        .num_inputs
+        .checked_add(circuit.num_outputs)
+        .and_then(|n| n.checked_add(1))
+        .ok_or_else(|| malformed("too many public signals".to_string()))?;
+    let num_variables = circuit.num_variables;
+    let num_aux = num_variables
+        .checked_sub(num_inputs)
+        .ok_or_else(|| malformed("fewer wires than public signals".to_string()))?;
+
+    let linear_combination = |lc: &BTreeMap<String, String>| {
+        lc.iter()
+            .map(|(wire, coeff)| {
+                let wire = wire
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|&wire| wire < num_variables)
+                    .ok_or_else(|| malformed(format!("invalid wire {:?}", wire)))?;
+                Ok((wire, from_decimal::<Fr>(coeff)?))
+            })
+            .collect::<Result<Vec<_>, NovaScotiaError>>()
+    };
+    let constraints = circuit
+        .constraints
+        .iter()
+        .enumerate()
+        .map(|(i, constraint)| match constraint.as_slice() {
+            [a, b, c] => Ok((
+                linear_combination(a)?,
+                linear_combination(b)?,
+                linear_combination(c)?,
+            )),
+            _ => Err(malformed(format!(
+                "constraint {} has {} linear combinations instead of 3",
+                i,
+                constraint.len()
+            ))),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(R1CS {
         num_aux,
         num_inputs,
+        num_outputs: circuit.num_outputs,
         num_variables,
-        constraints: file.constraints,
+        constraints,
+        io: IoLayout::symmetric(num_inputs),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circom::prime::modulus;
+    use crate::convert::to_decimal;
+
+    type Fr = <nova_snark::provider::PallasEngine as Engine>::Scalar;
+
+    fn wtns_bytes(witness: &[u64]) -> Vec<u8> {
+        let mut buf = b"wtns".to_vec();
+        buf.extend(2u32.to_le_bytes());
+        buf.extend(2u32.to_le_bytes());
+        buf.extend(1u32.to_le_bytes());
+        buf.extend(40u64.to_le_bytes());
+        buf.extend(32u32.to_le_bytes());
+        buf.extend(modulus::<Fr>().to_bytes_le());
+        buf.extend((witness.len() as u32).to_le_bytes());
+        buf.extend(2u32.to_le_bytes());
+        buf.extend((witness.len() as u64 * 32).to_le_bytes());
+        for w in witness {
+            buf.extend(Fr::from(*w).to_repr().as_ref());
+        }
+        buf
+    }
+
+    // the fake witness generators are shell scripts
+    #[cfg(unix)]
+    #[test]
+    fn test_concurrent_witness_generation() {
+        use std::os::unix::fs::PermissionsExt;
+        use std::thread;
+
+        // a fake witness generator that answers slowly with a witness chosen by its input
+        let dir = tempfile::tempdir().unwrap();
+        for i in 0..8u64 {
+            fs::write(
+                dir.path().join(format!("{}.wtns", i)),
+                wtns_bytes(&[1, i, i * i]),
+            )
+            .unwrap();
+        }
+        let witness_bin = dir.path().join("generator.sh");
+        fs::write(
+            &witness_bin,
+            format!(
+                "#!/bin/sh\nn=$(sed -e 's/.*\"adder\":\\([0-9]*\\).*/\\1/' \"$1\")\nsleep 0.2\ncp {}/$n.wtns \"$2\"\n",
+                dir.path().display()
+            ),
+        )
+        .unwrap();
+        fs::set_permissions(&witness_bin, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let handles: Vec<_> = (0..8u64)
+            .map(|i| {
+                let witness_bin = witness_bin.clone();
+                thread::spawn(move || {
+                    let input = format!("{{\"adder\":{}}}", i);
+                    (
+                        i,
+                        generate_witness_from_bin::<Fr>(&witness_bin, &input).unwrap(),
+                    )
+                })
+            })
+            .collect();
+        for handle in handles {
+            let (i, witness) = handle.join().unwrap();
+            assert_eq!(witness, vec![Fr::from(1), Fr::from(i), Fr::from(i * i)]);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_failing_witness_generator() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let witness_bin = dir.path().join("generator.sh");
+        fs::write(
+            &witness_bin,
+            "#!/bin/sh\necho computing\necho 'Assert Failed.' >&2\nexit 3\n",
+        )
+        .unwrap();
+        fs::set_permissions(&witness_bin, fs::Permissions::from_mode(0o755)).unwrap();
+
+        match generate_witness_from_bin::<Fr>(&witness_bin, "{}") {
+            Err(NovaScotiaError::WitnessGenerator {
+                status,
+                stdout,
+                stderr,
+            }) => {
+                assert_eq!(status, Some(3));
+                assert_eq!(stdout, "computing\n");
+                assert_eq!(stderr, "Assert Failed.\n");
+            }
+            res => panic!("unexpected result {:?}", res.map(|w| w.len())),
+        }
+    }
+
+    #[test]
+    fn test_engine_for_r1cs() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        assert_eq!(
+            engine_for_r1cs(&root.join("examples/toy/pasta/toy.r1cs")).unwrap(),
+            Some(SupportedEngine::Pallas)
+        );
+        assert_eq!(
+            engine_for_r1cs(&root.join("examples/toy/bn254/toy.r1cs")).unwrap(),
+            Some(SupportedEngine::Bn256)
+        );
+    }
+
+    #[test]
+    fn test_witness_prime_mismatch() {
+        type Other = <nova_snark::provider::VestaEngine as Engine>::Scalar;
+        let buf = wtns_bytes(&[1, 2, 3]);
+        assert_eq!(load_witness_from_array::<Fr>(buf.clone()).unwrap().len(), 3);
+        assert!(matches!(
+            load_witness_from_array::<Other>(buf),
+            Err(NovaScotiaError::FieldMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_witness_field_size() {
+        // elements padded to 40 bytes, as circom would write them for a wider prime
+        let mut buf = b"wtns".to_vec();
+        buf.extend(2u32.to_le_bytes());
+        buf.extend(2u32.to_le_bytes());
+        buf.extend(1u32.to_le_bytes());
+        buf.extend(48u64.to_le_bytes());
+        buf.extend(40u32.to_le_bytes());
+        let mut prime = modulus::<Fr>().to_bytes_le();
+        prime.resize(40, 0);
+        buf.extend(prime);
+        buf.extend(2u32.to_le_bytes());
+        buf.extend(2u32.to_le_bytes());
+        buf.extend(80u64.to_le_bytes());
+        for w in [1u64, 42] {
+            let mut element = Fr::from(w).to_repr().as_ref().to_vec();
+            element.resize(40, 0);
+            buf.extend(element);
+        }
+        assert_eq!(
+            load_witness_from_array::<Fr>(buf.clone()).unwrap(),
+            vec![Fr::from(1), Fr::from(42)]
+        );
+
+        // a value that does not fit in the field is rejected
+        let last = buf.len() - 1;
+        buf[last] = 1;
+        assert!(matches!(
+            load_witness_from_array::<Fr>(buf),
+            Err(NovaScotiaError::NonCanonicalFieldElement(_))
+        ));
+    }
+
+    #[test]
+    fn test_witness_huge_sizes() {
+        // a few bytes claiming a 4 GB prime
+        let mut buf = b"wtns".to_vec();
+        buf.extend(2u32.to_le_bytes());
+        buf.extend(2u32.to_le_bytes());
+        buf.extend(1u32.to_le_bytes());
+        buf.extend((8 + 0xffff_fff0u64).to_le_bytes());
+        buf.extend(0xffff_fff0u32.to_le_bytes());
+        buf.extend([1, 2, 3]);
+        assert!(matches!(
+            load_witness_from_array::<Fr>(buf),
+            Err(NovaScotiaError::MalformedWitness(_))
+        ));
+
+        // and a witness claiming 4 billion elements
+        let mut buf = wtns_bytes(&[1]);
+        buf.truncate(buf.len() - 32 - 12);
+        buf.extend(u32::MAX.to_le_bytes());
+        buf.extend(2u32.to_le_bytes());
+        buf.extend((u32::MAX as u64 * 32).to_le_bytes());
+        buf.extend(Fr::from(1).to_repr().as_ref());
+        assert!(matches!(
+            load_witness_from_array::<Fr>(buf),
+            Err(NovaScotiaError::MalformedWitness(_))
+        ));
+    }
+
+    #[test]
+    fn test_load_r1cs_with_symbols() {
+        type G1 = nova_snark::provider::PallasEngine;
+        type G2 = nova_snark::provider::VestaEngine;
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let r1cs = load_r1cs_with_symbols::<G1, G2>(
+            &FileLocation::PathBuf(root.join("examples/toy/pasta/toy.r1cs")),
+            &root.join("examples/toy/pasta/toy.sym"),
+        )
+        .unwrap();
+        assert_eq!(r1cs.io, IoLayout::symmetric(r1cs.num_inputs));
+        assert_eq!(r1cs.io.step_in, vec![3, 4]);
+    }
+
+    #[test]
+    fn test_check_toy_witness() {
+        use crate::circom::circuit::CircomCircuit;
+        use std::sync::Arc;
+        type G1 = nova_snark::provider::PallasEngine;
+        type G2 = nova_snark::provider::VestaEngine;
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let r1cs_file = root.join("examples/toy/pasta/toy.r1cs");
+        let r1cs = load_r1cs::<G1, G2>(&FileLocation::PathBuf(r1cs_file.clone())).unwrap();
+        let names = load_wire_names::<G1, G2>(&r1cs_file, &root.join("examples/toy/pasta/toy.sym"))
+            .unwrap();
+        assert_eq!(names.get(2), Some("main.step_out[1]"));
+
+        // step_out[1] <== step_in[0] + step_in[1], off by one
+        let witness = [1, 10, 21, 10, 10].map(Fr::from).to_vec();
+        let circuit = CircomCircuit {
+            r1cs: Arc::new(r1cs),
+            witness: Some(witness),
+        };
+        let err = circuit
+            .check_witness(Some(&names))
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(err.contains("main.step_out[1] (wire 2) = 21"), "{}", err);
+    }
+
+    #[test]
+    fn test_load_r1cs_from_json() {
+        type G1 = nova_snark::provider::PallasEngine;
+        type G2 = nova_snark::provider::VestaEngine;
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let r1cs = load_r1cs::<G1, G2>(&FileLocation::PathBuf(
+            root.join("examples/toy/pasta/toy.r1cs"),
+        ))
+        .unwrap();
+
+        // the toy circuit as `snarkjs r1cs export json` writes it
+        let lc = |lc: &Vec<(usize, Fr)>| {
+            lc.iter()
+                .map(|(wire, coeff)| (wire.to_string(), to_decimal(coeff)))
+                .collect::<BTreeMap<_, _>>()
+        };
+        let circuit = CircuitJson {
+            constraints: r1cs
+                .constraints
+                .iter()
+                .map(|(a, b, c)| vec![lc(a), lc(b), lc(c)])
+                .collect(),
+            num_inputs: 2,
+            num_outputs: 2,
+            num_variables: r1cs.num_variables,
+            prime: Some(modulus::<Fr>().to_str_radix(10)),
+            use_custom_gates: false,
+        };
+        let json = serde_json::to_string_pretty(&circuit).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["toy.json", "toy"] {
+            let path = dir.path().join(name);
+            fs::write(&path, &json).unwrap();
+            let loaded = load_r1cs::<G1, G2>(&FileLocation::PathBuf(path)).unwrap();
+            assert_eq!(loaded.constraints, r1cs.constraints);
+            assert_eq!(loaded.num_inputs, r1cs.num_inputs);
+            assert_eq!(loaded.num_aux, r1cs.num_aux);
+            assert_eq!(loaded.io, r1cs.io);
+        }
+
+        type Other = <nova_snark::provider::VestaEngine as Engine>::Scalar;
+        assert!(matches!(
+            r1cs_from_json::<Other>(serde_json::from_str(&json).unwrap()),
+            Err(NovaScotiaError::FieldMismatch { .. })
+        ));
+        let non_canonical = json.replacen(
+            &format!("\"{}\"", to_decimal(&-Fr::from(1))),
+            &format!("\"{}\"", modulus::<Fr>()),
+            1,
+        );
+        assert_ne!(non_canonical, json);
+        assert!(matches!(
+            r1cs_from_json::<Fr>(serde_json::from_str(&non_canonical).unwrap()),
+            Err(NovaScotiaError::NonCanonicalFieldElement(_))
+        ));
+    }
+
+    #[test]
+    fn test_load_witness_from_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("witness.json");
+        let witness = vec![Fr::from(1), Fr::from(10), -Fr::from(20)];
+        let values = witness.iter().map(to_decimal).collect::<Vec<_>>();
+        fs::write(&path, serde_json::to_string(&values).unwrap()).unwrap();
+        assert_eq!(load_witness_from_file::<Fr>(&path).unwrap(), witness);
+
+        // the binary format is still recognised by its magic
+        let path = dir.path().join("witness.wtns");
+        fs::write(&path, wtns_bytes(&[1, 2])).unwrap();
+        assert_eq!(
+            load_witness_from_file::<Fr>(&path).unwrap(),
+            vec![Fr::from(1), Fr::from(2)]
+        );
+
+        for value in ["", "-1", "0x10", "010", &modulus::<Fr>().to_string()] {
+            let json = serde_json::to_vec(&["1", value]).unwrap();
+            assert!(
+                matches!(
+                    load_witness_from_json_reader::<Fr, _>(json.as_slice()),
+                    Err(NovaScotiaError::NonCanonicalFieldElement(_))
+                ),
+                "{:?}",
+                value
+            );
+        }
     }
 }
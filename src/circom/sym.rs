@@ -0,0 +1,160 @@
+// Parser for the `.sym` debug symbol files emitted by `circom --sym`.
+//
+// Each line is `label_index,witness_index,component_index,name`, where a witness index of -1
+// marks a signal that was optimized away.
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use crate::error::NovaScotiaError;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub label: u64,
+    pub witness: Option<usize>,
+    pub component: u64,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SymbolTable {
+    pub symbols: Vec<Symbol>,
+    by_name: HashMap<String, usize>,
+    by_label: HashMap<u64, usize>,
+    by_witness: HashMap<usize, usize>,
+}
+
+fn malformed(line: usize, reason: &str) -> NovaScotiaError {
+    NovaScotiaError::InvalidInput(format!("malformed symbol file line {}: {}", line, reason))
+}
+
+impl SymbolTable {
+    /// load a `.sym` file by filename
+    pub fn from_file(filename: &Path) -> Result<Self, NovaScotiaError> {
+        Self::from_reader(BufReader::new(File::open(filename)?))
+    }
+
+    /// load a `.sym` file from a reader
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<Self, NovaScotiaError> {
+        let mut symbols = vec![];
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let mut fields = line.splitn(4, ',');
+            let mut next = |what: &str| fields.next().ok_or_else(|| malformed(i + 1, what));
+            let label = next("missing label index")?;
+            let witness = next("missing witness index")?;
+            let component = next("missing component index")?;
+            let name = next("missing signal name")?;
+
+            let label = label
+                .parse::<u64>()
+                .map_err(|_| malformed(i + 1, "invalid label index"))?;
+            let witness = match witness.parse::<i64>() {
+                Ok(-1) => None,
+                Ok(w) if w >= 0 => Some(w as usize),
+                _ => return Err(malformed(i + 1, "invalid witness index")),
+            };
+            let component = component
+                .parse::<u64>()
+                .map_err(|_| malformed(i + 1, "invalid component index"))?;
+            symbols.push(Symbol {
+                label,
+                witness,
+                component,
+                name: name.trim().to_string(),
+            });
+        }
+        Ok(Self::new(symbols))
+    }
+
+    pub fn new(symbols: Vec<Symbol>) -> Self {
+        let mut table = SymbolTable {
+            symbols,
+            ..Default::default()
+        };
+        for (i, symbol) in table.symbols.iter().enumerate() {
+            table.by_name.insert(symbol.name.clone(), i);
+            table.by_label.insert(symbol.label, i);
+            if let Some(w) = symbol.witness {
+                table.by_witness.insert(w, i);
+            }
+        }
+        table
+    }
+
+    /// look up a signal by its fully qualified name, e.g. `main.step_in[0]`
+    pub fn get(&self, name: &str) -> Option<&Symbol> {
+        self.by_name.get(name).map(|&i| &self.symbols[i])
+    }
+
+    /// look up the signal with the given label index
+    pub fn by_label(&self, label: u64) -> Option<&Symbol> {
+        self.by_label.get(&label).map(|&i| &self.symbols[i])
+    }
+
+    /// look up the signal stored at the given witness index
+    pub fn by_witness(&self, witness: usize) -> Option<&Symbol> {
+        self.by_witness.get(&witness).map(|&i| &self.symbols[i])
+    }
+
+    /// witness indices of the signal array `name[0]`, `name[1]`, ... (or the scalar `name`)
+    pub fn array_witnesses(&self, name: &str) -> Result<Vec<usize>, NovaScotiaError> {
+        let witness_of = |symbol: &Symbol| {
+            symbol.witness.ok_or_else(|| {
+                NovaScotiaError::InvalidInput(format!(
+                    "signal {} was optimized away by circom",
+                    symbol.name
+                ))
+            })
+        };
+        if let Some(symbol) = self.get(name) {
+            return Ok(vec![witness_of(symbol)?]);
+        }
+        let mut witnesses = vec![];
+        while let Some(symbol) = self.get(&format!("{}[{}]", name, witnesses.len())) {
+            witnesses.push(witness_of(symbol)?);
+        }
+        Ok(witnesses)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toy_symbols() {
+        let table = SymbolTable::from_file(
+            &Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/toy/pasta/toy.sym"),
+        )
+        .unwrap();
+        assert_eq!(table.symbols.len(), 5);
+        assert_eq!(
+            table.get("main.adder"),
+            Some(&Symbol {
+                label: 5,
+                witness: None,
+                component: 0,
+                name: "main.adder".to_string(),
+            })
+        );
+        assert_eq!(table.by_witness(3).unwrap().name, "main.step_in[0]");
+        assert_eq!(table.array_witnesses("main.step_out").unwrap(), vec![1, 2]);
+        assert_eq!(table.array_witnesses("main.step_in").unwrap(), vec![3, 4]);
+        assert!(table.array_witnesses("main.missing").unwrap().is_empty());
+        assert!(table.array_witnesses("main.adder").is_err());
+    }
+
+    #[test]
+    fn test_malformed_symbols() {
+        let err = SymbolTable::from_reader("1,1,0,main.a\n2,x,0,main.b\n".as_bytes()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid input: malformed symbol file line 2: invalid witness index"
+        );
+    }
+}
@@ -0,0 +1,464 @@
+// In-process witness generation for the `circuit.wasm` emitted by `circom --wasm`.
+// This is a native port of the host side of circom's `witness_calculator.js`.
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::circom::prime::check_prime;
+use crate::convert::{self, from_biguint, to_biguint};
+use crate::error::NovaScotiaError;
+use ff::PrimeField;
+use num_bigint::BigUint;
+use num_traits::Zero;
+use serde_json::Value;
+use wasmi::{Caller, Engine, Extern, Instance, Linker, Module, Store, TypedFunc};
+
+#[derive(Default)]
+struct HostState {
+    error_message: String,
+    log_message: String,
+}
+
+/// Witness calculator backed by a circom-generated WASM module.
+///
+/// The module is compiled and instantiated once and can compute any number of witnesses. Output
+/// of circom's `log()` is emitted at `info` level through the `log` crate.
+pub struct WitnessCalculator {
+    store: Store<HostState>,
+    instance: Instance,
+    n32: u32,
+    prime: BigUint,
+    witness_size: u32,
+}
+
+fn read_message(caller: &mut Caller<'_, HostState>) -> Result<String, wasmi::Error> {
+    let get_message_char = match caller.get_export("getMessageChar") {
+        Some(Extern::Func(f)) => f.typed::<(), i32>(&caller)?,
+        _ => return Err(wasmi::Error::new("missing export getMessageChar")),
+    };
+    let mut message = String::new();
+    loop {
+        let c = get_message_char.call(&mut *caller, ())?;
+        if c == 0 {
+            break;
+        }
+        message.push(c as u8 as char);
+    }
+    Ok(message)
+}
+
+fn exception_message(code: i32) -> &'static str {
+    match code {
+        1 => "Signal not found.",
+        2 => "Too many signals set.",
+        3 => "Signal already set.",
+        4 => "Assert Failed.",
+        5 => "Not enough memory.",
+        6 => "Input signal array access exceeds the size.",
+        _ => "Unknown error.",
+    }
+}
+
+fn linker(engine: &Engine) -> Result<Linker<HostState>, NovaScotiaError> {
+    let mut linker = Linker::new(engine);
+    linker.func_wrap(
+        "runtime",
+        "exceptionHandler",
+        |caller: Caller<'_, HostState>, code: i32| -> Result<(), wasmi::Error> {
+            Err(wasmi::Error::new(format!(
+                "{}\n{}",
+                exception_message(code),
+                caller.data().error_message
+            )))
+        },
+    )?;
+    linker.func_wrap(
+        "runtime",
+        "printErrorMessage",
+        |mut caller: Caller<'_, HostState>| -> Result<(), wasmi::Error> {
+            let message = read_message(&mut caller)?;
+            caller.data_mut().error_message.push_str(&message);
+            caller.data_mut().error_message.push('\n');
+            Ok(())
+        },
+    )?;
+    linker.func_wrap(
+        "runtime",
+        "writeBufferMessage",
+        |mut caller: Caller<'_, HostState>| -> Result<(), wasmi::Error> {
+            let message = read_message(&mut caller)?;
+            let state = caller.data_mut();
+            // Any calls to `log()` will always end with a `\n`, so that's when we log and reset
+            if message == "\n" {
+                log::info!("{}", state.log_message);
+                state.log_message.clear();
+            } else {
+                if !state.log_message.is_empty() {
+                    state.log_message.push(' ');
+                }
+                state.log_message.push_str(&message);
+            }
+            Ok(())
+        },
+    )?;
+    linker.func_wrap(
+        "runtime",
+        "showSharedRWMemory",
+        |mut caller: Caller<'_, HostState>| -> Result<(), wasmi::Error> {
+            let (n32, read) = match (
+                caller.get_export("getFieldNumLen32"),
+                caller.get_export("readSharedRWMemory"),
+            ) {
+                (Some(Extern::Func(n32)), Some(Extern::Func(read))) => (
+                    n32.typed::<(), i32>(&caller)?,
+                    read.typed::<i32, i32>(&caller)?,
+                ),
+                _ => return Err(wasmi::Error::new("missing shared memory exports")),
+            };
+            let n32 = n32.call(&mut caller, ())?;
+            let mut words = Vec::with_capacity(n32 as usize);
+            for j in 0..n32 {
+                words.push(read.call(&mut caller, j)? as u32);
+            }
+            let value = BigUint::from_slice(&words);
+            let state = caller.data_mut();
+            if !state.log_message.is_empty() {
+                state.log_message.push(' ');
+            }
+            state.log_message.push_str(&value.to_str_radix(10));
+            Ok(())
+        },
+    )?;
+    Ok(linker)
+}
+
+/// 64-bit FNV-1a hash of a signal name, split into (MSB, LSB) halves as circom expects.
+fn fnv_hash(name: &str) -> (i32, i32) {
+    let mut hash: u64 = 0xCBF29CE484222325;
+    for byte in name.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001B3);
+    }
+    ((hash >> 32) as u32 as i32, hash as u32 as i32)
+}
+
+impl WitnessCalculator {
+    /// load a witness calculator from a `circuit.wasm` file
+    pub fn from_file(path: &Path) -> Result<Self, NovaScotiaError> {
+        let code = fs::read(path)?;
+        Self::from_bytes(&code)
+    }
+
+    /// load a witness calculator from the bytes of a `circuit.wasm` module
+    pub fn from_bytes(code: &[u8]) -> Result<Self, NovaScotiaError> {
+        let engine = Engine::default();
+        let module = Module::new(&engine, code)?;
+        let mut store = Store::new(&engine, HostState::default());
+        let instance = linker(&engine)?.instantiate_and_start(&mut store, &module)?;
+
+        let mut calculator = WitnessCalculator {
+            store,
+            instance,
+            n32: 0,
+            prime: BigUint::zero(),
+            witness_size: 0,
+        };
+        calculator.n32 = calculator
+            .func::<(), i32>("getFieldNumLen32")?
+            .call(&mut calculator.store, ())? as u32;
+        calculator
+            .func::<(), ()>("getRawPrime")?
+            .call(&mut calculator.store, ())?;
+        calculator.prime = calculator.read_shared_memory()?;
+        calculator.witness_size = calculator
+            .func::<(), i32>("getWitnessSize")?
+            .call(&mut calculator.store, ())? as u32;
+        Ok(calculator)
+    }
+
+    fn func<Params, Results>(
+        &self,
+        name: &str,
+    ) -> Result<TypedFunc<Params, Results>, NovaScotiaError>
+    where
+        Params: wasmi::WasmParams,
+        Results: wasmi::WasmResults,
+    {
+        Ok(self
+            .instance
+            .get_typed_func::<Params, Results>(&self.store, name)?)
+    }
+
+    fn read_shared_memory(&mut self) -> Result<BigUint, NovaScotiaError> {
+        let read = self.func::<i32, i32>("readSharedRWMemory")?;
+        let mut words = Vec::with_capacity(self.n32 as usize);
+        for j in 0..self.n32 {
+            words.push(read.call(&mut self.store, j as i32)? as u32);
+        }
+        Ok(BigUint::from_slice(&words))
+    }
+
+    fn write_shared_memory(&mut self, value: &BigUint) -> Result<(), NovaScotiaError> {
+        let write = self.func::<(i32, i32), ()>("writeSharedRWMemory")?;
+        let mut words = value.to_u32_digits();
+        words.resize(self.n32 as usize, 0);
+        for (j, word) in words.into_iter().enumerate() {
+            write.call(&mut self.store, (j as i32, word as i32))?;
+        }
+        Ok(())
+    }
+
+    /// the prime of the field the circuit was compiled for
+    pub fn prime(&self) -> &BigUint {
+        &self.prime
+    }
+
+    /// the number of field elements in a witness produced by this circuit
+    pub fn witness_size(&self) -> usize {
+        self.witness_size as usize
+    }
+
+    fn set_inputs<Fr: PrimeField>(
+        &mut self,
+        inputs: &HashMap<String, Value>,
+    ) -> Result<(), NovaScotiaError> {
+        self.store.data_mut().error_message.clear();
+        self.func::<i32, ()>("init")?.call(&mut self.store, 0)?;
+
+        let get_input_signal_size = self.func::<(i32, i32), i32>("getInputSignalSize")?;
+        let set_input_signal = self.func::<(i32, i32, i32), ()>("setInputSignal")?;
+
+        let mut input_counter = 0;
+        for (name, value) in inputs {
+            let (h_msb, h_lsb) = fnv_hash(name);
+            let values = convert::from_json::<Fr>(value).map_err(|err| {
+                NovaScotiaError::InvalidInput(format!("input signal {}: {}", name, err))
+            })?;
+
+            let signal_size = get_input_signal_size.call(&mut self.store, (h_msb, h_lsb))?;
+            if signal_size < 0 {
+                return Err(NovaScotiaError::Wasm(format!("Signal {} not found", name)));
+            }
+            if values.len() < signal_size as usize {
+                return Err(NovaScotiaError::Wasm(format!(
+                    "Not enough values for input signal {}",
+                    name
+                )));
+            }
+            if values.len() > signal_size as usize {
+                return Err(NovaScotiaError::Wasm(format!(
+                    "Too many values for input signal {}",
+                    name
+                )));
+            }
+            for (i, value) in values.iter().enumerate() {
+                self.write_shared_memory(&to_biguint(value))?;
+                set_input_signal
+                    .call(&mut self.store, (h_msb, h_lsb, i as i32))
+                    .map_err(|e| {
+                        NovaScotiaError::Wasm(format!(
+                            "failed to set input signal {}[{}]: {}",
+                            name, i, e
+                        ))
+                    })?;
+                input_counter += 1;
+            }
+        }
+
+        let input_size = self
+            .func::<(), i32>("getInputSize")?
+            .call(&mut self.store, ())?;
+        if input_counter < input_size {
+            return Err(NovaScotiaError::Wasm(format!(
+                "Not all inputs have been set. Only {} out of {}",
+                input_counter, input_size
+            )));
+        }
+        Ok(())
+    }
+
+    /// run the circuit on the given circom input signals, after which
+    /// [`witness_values`](Self::witness_values) reads the signals of the resulting witness
+    pub fn compute<Fr: PrimeField>(
+        &mut self,
+        inputs: &HashMap<String, Value>,
+    ) -> Result<(), NovaScotiaError> {
+        check_prime::<Fr>(&self.prime.to_bytes_le())?;
+        self.set_inputs::<Fr>(inputs)
+    }
+
+    /// the values at `indices` of the witness of the last [`compute`](Self::compute)
+    pub fn witness_values<Fr: PrimeField>(
+        &mut self,
+        indices: impl IntoIterator<Item = usize>,
+    ) -> Result<Vec<Fr>, NovaScotiaError> {
+        let get_witness = self.func::<i32, ()>("getWitness")?;
+        let mut values = vec![];
+        for i in indices {
+            if i >= self.witness_size() {
+                return Err(NovaScotiaError::Wasm(format!(
+                    "witness index {} is out of range for a witness of {} values",
+                    i, self.witness_size
+                )));
+            }
+            get_witness.call(&mut self.store, i as i32)?;
+            let value = self.read_shared_memory()?;
+            values.push(from_biguint::<Fr>(&value)?);
+        }
+        Ok(values)
+    }
+
+    /// compute the full witness for the given circom input signals
+    pub fn calculate_witness<Fr: PrimeField>(
+        &mut self,
+        inputs: &HashMap<String, Value>,
+    ) -> Result<Vec<Fr>, NovaScotiaError> {
+        self.compute::<Fr>(inputs)?;
+        self.witness_values(0..self.witness_size())
+    }
+}
+
+/// compute a witness in-process from a circom `circuit.wasm` and a JSON input
+///
+/// This compiles the module on every call; keep a [`WitnessCalculator`] to compute several
+/// witnesses of the same circuit.
+pub fn generate_witness_from_wasm<Fr: PrimeField>(
+    witness_wasm: &Path,
+    witness_input_json: &str,
+) -> Result<Vec<Fr>, NovaScotiaError> {
+    let inputs: HashMap<String, Value> = serde_json::from_str(witness_input_json)?;
+    let mut calculator = WitnessCalculator::from_file(witness_wasm)?;
+    calculator.calculate_witness(&inputs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circom::reader::{generate_witness_from_bin, load_r1cs, load_witness_from_file};
+    use crate::FileLocation;
+    use ff::Field;
+    use nova_snark::provider::{Bn256EngineIPA, GrumpkinEngine, PallasEngine, VestaEngine};
+    use nova_snark::traits::Engine as NovaEngine;
+    use serde_json::json;
+
+    type Fr = <PallasEngine as NovaEngine>::Scalar;
+
+    fn toy_dir(group_name: &str) -> std::path::PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join(format!("examples/toy/{}", group_name))
+    }
+
+    fn toy_inputs(group_name: &str) -> HashMap<String, Value> {
+        let input = fs::read_to_string(toy_dir(group_name).join("toy_js/input.json")).unwrap();
+        serde_json::from_str(&input).unwrap()
+    }
+
+    #[test]
+    fn test_fnv_hash() {
+        assert_eq!(
+            fnv_hash("step_in"),
+            (0xbed81b99u32 as i32, 0xc7d4de4fu32 as i32)
+        );
+        assert_eq!(
+            fnv_hash("adder"),
+            (0x235decfdu32 as i32, 0xfd543243u32 as i32)
+        );
+    }
+
+    // the witness of the circom-generated toy.wasm is the one circom's generate_witness.js wrote
+    // for the same input, and satisfies the checked-in toy circuit
+    fn toy_witness<G1, G2>(group_name: &str)
+    where
+        G1: NovaEngine<Base = <G2 as NovaEngine>::Scalar>,
+        G2: NovaEngine<Base = <G1 as NovaEngine>::Scalar>,
+    {
+        let dir = toy_dir(group_name);
+        let mut calculator = WitnessCalculator::from_file(&dir.join("toy_js/toy.wasm")).unwrap();
+        assert_eq!(calculator.witness_size(), 5);
+
+        let witness = calculator
+            .calculate_witness::<G1::Scalar>(&toy_inputs(group_name))
+            .unwrap();
+        let expected =
+            load_witness_from_file::<G1::Scalar>(&dir.join("toy_js/witness.wtns")).unwrap();
+        assert_eq!(witness, expected);
+        assert_eq!(
+            witness,
+            [1u64, 13, 22, 10, 12].map(G1::Scalar::from).to_vec()
+        );
+
+        let r1cs = load_r1cs::<G1, G2>(&FileLocation::PathBuf(dir.join("toy.r1cs"))).unwrap();
+        let eval = |lc: &Vec<(usize, G1::Scalar)>| {
+            lc.iter().fold(G1::Scalar::ZERO, |acc, (i, coeff)| {
+                acc + *coeff * witness[*i]
+            })
+        };
+        for (a, b, c) in r1cs.constraints.iter() {
+            assert_eq!(eval(a) * eval(b), eval(c));
+        }
+
+        // the calculator can be reused across steps
+        let inputs: HashMap<String, Value> = serde_json::from_value(json!({
+            "step_in": [13, 22],
+            "adder": 1,
+        }))
+        .unwrap();
+        let witness = calculator.calculate_witness::<G1::Scalar>(&inputs).unwrap();
+        assert_eq!(witness[1], G1::Scalar::from(14));
+        assert_eq!(witness[2], G1::Scalar::from(35));
+
+        // single signals can be read without extracting the whole witness
+        calculator.compute::<G1::Scalar>(&inputs).unwrap();
+        assert_eq!(
+            calculator.witness_values::<G1::Scalar>([2, 1]).unwrap(),
+            [35u64, 14].map(G1::Scalar::from).to_vec()
+        );
+        assert!(calculator.witness_values::<G1::Scalar>([5]).is_err());
+    }
+
+    #[test]
+    fn test_toy_witness() {
+        toy_witness::<PallasEngine, VestaEngine>("pasta");
+        toy_witness::<Bn256EngineIPA, GrumpkinEngine>("bn254");
+    }
+
+    fn toy_witness_matches_cpp<Fr: PrimeField>(group_name: &str) {
+        let dir = toy_dir(group_name);
+        let input = json!(toy_inputs(group_name)).to_string();
+        assert_eq!(
+            generate_witness_from_wasm::<Fr>(&dir.join("toy_js/toy.wasm"), &input).unwrap(),
+            generate_witness_from_bin::<Fr>(&dir.join("toy_cpp/toy"), &input).unwrap()
+        );
+    }
+
+    #[test]
+    #[ignore = "needs the toy C++ witness generators built by examples/toy/compile.sh"]
+    fn test_toy_witness_matches_cpp() {
+        toy_witness_matches_cpp::<Fr>("pasta");
+        toy_witness_matches_cpp::<<Bn256EngineIPA as NovaEngine>::Scalar>("bn254");
+    }
+
+    #[test]
+    fn test_input_errors() {
+        let mut calculator =
+            WitnessCalculator::from_file(&toy_dir("pasta").join("toy_js/toy.wasm")).unwrap();
+        // circom's toy.wasm reports a size of 0 for signals it does not know, like
+        // witness_calculator.js does
+        let inputs: HashMap<String, Value> = serde_json::from_value(json!({
+            "step_in": [1, 1],
+            "unknown": 1,
+        }))
+        .unwrap();
+        let err = calculator.calculate_witness::<Fr>(&inputs).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Too many values for input signal unknown"),
+            "{}",
+            err
+        );
+
+        let inputs: HashMap<String, Value> =
+            serde_json::from_value(json!({ "step_in": [1, 1] })).unwrap();
+        let err = calculator.calculate_witness::<Fr>(&inputs).unwrap_err();
+        assert!(err.to_string().contains("Only 2 out of 3"), "{}", err);
+    }
+}
@@ -0,0 +1,296 @@
+// Writers for the `.r1cs` (version 1) and `.wtns` (version 2) binary formats read by
+// `circom::file` and `circom::reader`, laid out the way circom and snarkjs write them.
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use byteorder::{LittleEndian, WriteBytesExt};
+use ff::PrimeField;
+
+use crate::circom::circuit::{Constraint, R1CS};
+use crate::circom::file::{
+    CustomGate, CustomGateUse, Header, R1CSFile, CONSTRAINT_TYPE, CUSTOM_GATES_LIST_TYPE,
+    CUSTOM_GATES_USES_TYPE, HEADER_TYPE, WIRE2LABEL_TYPE,
+};
+use crate::circom::prime::modulus;
+use crate::error::NovaScotiaError;
+
+type Result<T> = std::result::Result<T, NovaScotiaError>;
+
+/// bytes circom uses for an element of `Fr`: the modulus rounded up to whole 64-bit words
+fn field_size<Fr: PrimeField>() -> u32 {
+    (modulus::<Fr>().bits() as u32).div_ceil(64) * 8
+}
+
+/// write a field element in `field_size` little-endian bytes
+fn write_field<W: Write, Fr: PrimeField>(mut writer: W, value: &Fr, field_size: u32) -> Result<()> {
+    let repr = value.to_repr();
+    let bytes = repr.as_ref();
+    let len = bytes.len().min(field_size as usize);
+    if bytes[len..].iter().any(|&byte| byte != 0) {
+        return Err(NovaScotiaError::InvalidInput(format!(
+            "field element does not fit in {} bytes",
+            field_size
+        )));
+    }
+    writer.write_all(&bytes[..len])?;
+    for _ in len..field_size as usize {
+        writer.write_u8(0)?;
+    }
+    Ok(())
+}
+
+fn write_section<W: Write>(mut writer: W, section_type: u32, section: &[u8]) -> Result<()> {
+    writer.write_u32::<LittleEndian>(section_type)?;
+    writer.write_u64::<LittleEndian>(section.len() as u64)?;
+    writer.write_all(section)?;
+    Ok(())
+}
+
+fn header_section(header: &Header) -> Result<Vec<u8>> {
+    let mut section = vec![];
+    section.write_u32::<LittleEndian>(header.field_size)?;
+    section.write_all(&header.prime_size)?;
+    section.write_u32::<LittleEndian>(header.n_wires)?;
+    section.write_u32::<LittleEndian>(header.n_pub_out)?;
+    section.write_u32::<LittleEndian>(header.n_pub_in)?;
+    section.write_u32::<LittleEndian>(header.n_prv_in)?;
+    section.write_u64::<LittleEndian>(header.n_labels)?;
+    section.write_u32::<LittleEndian>(header.n_constraints)?;
+    Ok(section)
+}
+
+fn constraints_section<Fr: PrimeField>(
+    constraints: &[Constraint<Fr>],
+    field_size: u32,
+) -> Result<Vec<u8>> {
+    let mut section = vec![];
+    for (a, b, c) in constraints {
+        for lc in [a, b, c] {
+            section.write_u32::<LittleEndian>(lc.len() as u32)?;
+            for (wire, coeff) in lc {
+                section.write_u32::<LittleEndian>(*wire as u32)?;
+                write_field(&mut section, coeff, field_size)?;
+            }
+        }
+    }
+    Ok(section)
+}
+
+fn custom_gates_sections<Fr: PrimeField>(
+    gates: &[CustomGate<Fr>],
+    uses: &[CustomGateUse],
+    field_size: u32,
+) -> Result<(Vec<u8>, Vec<u8>)> {
+    let mut list = vec![];
+    list.write_u32::<LittleEndian>(gates.len() as u32)?;
+    for gate in gates {
+        list.write_all(gate.template_name.as_bytes())?;
+        list.write_u8(0)?;
+        list.write_u32::<LittleEndian>(gate.parameters.len() as u32)?;
+        for parameter in &gate.parameters {
+            write_field(&mut list, parameter, field_size)?;
+        }
+    }
+    let mut applications = vec![];
+    applications.write_u32::<LittleEndian>(uses.len() as u32)?;
+    for gate in uses {
+        applications.write_u32::<LittleEndian>(gate.id)?;
+        applications.write_u32::<LittleEndian>(gate.signals.len() as u32)?;
+        for &signal in &gate.signals {
+            applications.write_u64::<LittleEndian>(signal)?;
+        }
+    }
+    Ok((list, applications))
+}
+
+/// write a parsed R1CS file back out, byte for byte as circom wrote it
+pub fn write_r1cs_file<W: Write, Fr: PrimeField>(mut writer: W, file: &R1CSFile<Fr>) -> Result<()> {
+    let header = &file.header;
+    if header.n_constraints as usize != file.constraints.len()
+        || header.n_wires as usize != file.wire_mapping.len()
+        || header.prime_size.len() != header.field_size as usize
+    {
+        return Err(NovaScotiaError::InvalidInput(
+            "R1CS file header does not match its contents".to_string(),
+        ));
+    }
+    let custom_gates = !file.custom_gates.is_empty() || !file.custom_gate_uses.is_empty();
+
+    writer.write_all(b"r1cs")?;
+    writer.write_u32::<LittleEndian>(1)?;
+    writer.write_u32::<LittleEndian>(if custom_gates { 5 } else { 3 })?;
+    // circom writes the constraints before the header
+    write_section(
+        &mut writer,
+        CONSTRAINT_TYPE,
+        &constraints_section(&file.constraints, header.field_size)?,
+    )?;
+    write_section(&mut writer, HEADER_TYPE, &header_section(header)?)?;
+    let mut wire_mapping = vec![];
+    for &label in &file.wire_mapping {
+        wire_mapping.write_u64::<LittleEndian>(label)?;
+    }
+    write_section(&mut writer, WIRE2LABEL_TYPE, &wire_mapping)?;
+    if custom_gates {
+        let (list, applications) = custom_gates_sections(
+            &file.custom_gates,
+            &file.custom_gate_uses,
+            header.field_size,
+        )?;
+        write_section(&mut writer, CUSTOM_GATES_LIST_TYPE, &list)?;
+        write_section(&mut writer, CUSTOM_GATES_USES_TYPE, &applications)?;
+    }
+    Ok(())
+}
+
+impl<Fr: PrimeField> R1CSFile<Fr> {
+    /// the R1CS file of a constraint system, with its `num_outputs` public outputs followed by
+    /// the remaining public signals as public inputs
+    ///
+    /// `R1CS` does not keep circom's labels or its count of private inputs, so every wire is
+    /// labelled with its own index and the private inputs are counted as intermediate signals.
+    pub fn from_r1cs(r1cs: &R1CS<Fr>) -> Result<Self> {
+        let field_size = field_size::<Fr>();
+        let mut prime_size = modulus::<Fr>().to_bytes_le();
+        prime_size.resize(field_size as usize, 0);
+        let n_pub_in = r1cs
+            .num_inputs
+            .checked_sub(1 + r1cs.num_outputs)
+            .ok_or_else(|| {
+                NovaScotiaError::MalformedR1CS(format!(
+                    "{} public outputs do not fit in {} public signals",
+                    r1cs.num_outputs,
+                    r1cs.num_inputs.saturating_sub(1)
+                ))
+            })?;
+        Ok(R1CSFile {
+            version: 1,
+            header: Header {
+                field_size,
+                prime_size,
+                n_wires: r1cs.num_variables as u32,
+                n_pub_out: r1cs.num_outputs as u32,
+                n_pub_in: n_pub_in as u32,
+                n_prv_in: 0,
+                n_labels: r1cs.num_variables as u64,
+                n_constraints: r1cs.constraints.len() as u32,
+            },
+            constraints: r1cs.constraints.clone(),
+            wire_mapping: (0..r1cs.num_variables as u64).collect(),
+            custom_gates: vec![],
+            custom_gate_uses: vec![],
+        })
+    }
+}
+
+/// write a constraint system as an R1CS file, see [`R1CSFile::from_r1cs`]
+pub fn write_r1cs<W: Write, Fr: PrimeField>(writer: W, r1cs: &R1CS<Fr>) -> Result<()> {
+    write_r1cs_file(writer, &R1CSFile::from_r1cs(r1cs)?)
+}
+
+/// save a constraint system as an R1CS file at `path`
+pub fn save_r1cs<Fr: PrimeField>(r1cs: &R1CS<Fr>, path: &Path) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_r1cs(&mut writer, r1cs)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// write a witness in the `.wtns` version 2 format that snarkjs reads
+pub fn write_witness<W: Write, Fr: PrimeField>(mut writer: W, witness: &[Fr]) -> Result<()> {
+    let field_size = field_size::<Fr>();
+    let mut prime = modulus::<Fr>().to_bytes_le();
+    prime.resize(field_size as usize, 0);
+
+    writer.write_all(b"wtns")?;
+    writer.write_u32::<LittleEndian>(2)?;
+    writer.write_u32::<LittleEndian>(2)?;
+
+    writer.write_u32::<LittleEndian>(1)?;
+    writer.write_u64::<LittleEndian>(4 + field_size as u64 + 4)?;
+    writer.write_u32::<LittleEndian>(field_size)?;
+    writer.write_all(&prime)?;
+    writer.write_u32::<LittleEndian>(witness.len() as u32)?;
+
+    writer.write_u32::<LittleEndian>(2)?;
+    writer.write_u64::<LittleEndian>(witness.len() as u64 * field_size as u64)?;
+    for value in witness {
+        write_field(&mut writer, value, field_size)?;
+    }
+    Ok(())
+}
+
+/// save a witness as a `.wtns` file at `path`
+pub fn save_witness<Fr: PrimeField>(witness: &[Fr], path: &Path) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_witness(&mut writer, witness)?;
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circom::circuit::IoLayout;
+    use crate::circom::file::read_r1cs_file;
+    use nova_snark::provider::{Bn256EngineIPA, PallasEngine};
+    use nova_snark::traits::Engine;
+    use std::io::Cursor;
+
+    fn round_trip<Fr: PrimeField>(group_name: &str) {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join(format!("examples/toy/{}/toy.r1cs", group_name));
+        let bytes = std::fs::read(path).unwrap();
+        let file = read_r1cs_file::<_, Fr>(Cursor::new(&bytes)).unwrap();
+        let mut written = vec![];
+        write_r1cs_file(&mut written, &file).unwrap();
+        assert_eq!(written, bytes);
+
+        // a constraint system survives the trip through a file, keeping circom's public outputs
+        // even when `step_out` is located by name
+        let mut r1cs = file.into_r1cs().unwrap();
+        r1cs.io = IoLayout {
+            step_out: vec![1],
+            step_in: vec![3],
+        };
+        let mut written = vec![];
+        write_r1cs(&mut written, &r1cs).unwrap();
+        let file = read_r1cs_file::<_, Fr>(Cursor::new(&written)).unwrap();
+        assert_eq!(file.header.n_pub_out, 2);
+        assert_eq!(file.header.n_pub_in, 2);
+        assert_eq!(file.constraints, r1cs.constraints);
+
+        r1cs.num_outputs = r1cs.num_inputs;
+        assert!(matches!(
+            write_r1cs(&mut vec![], &r1cs),
+            Err(NovaScotiaError::MalformedR1CS(_))
+        ));
+    }
+
+    #[test]
+    fn test_r1cs_round_trip() {
+        round_trip::<<PallasEngine as Engine>::Scalar>("pasta");
+        round_trip::<<Bn256EngineIPA as Engine>::Scalar>("bn254");
+    }
+
+    #[cfg(feature = "prover")]
+    fn witness_round_trip<Fr: PrimeField>(group_name: &str) {
+        use crate::circom::reader::load_witness_from_array;
+
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join(format!("examples/toy/{}/toy_js/witness.wtns", group_name));
+        let bytes = std::fs::read(path).unwrap();
+        let witness = load_witness_from_array::<Fr>(bytes.clone()).unwrap();
+        let mut written = vec![];
+        write_witness(&mut written, &witness).unwrap();
+        assert_eq!(written, bytes);
+    }
+
+    #[cfg(feature = "prover")]
+    #[test]
+    fn test_witness_round_trip() {
+        witness_round_trip::<<PallasEngine as Engine>::Scalar>("pasta");
+        witness_round_trip::<<Bn256EngineIPA as Engine>::Scalar>("bn254");
+    }
+}
@@ -0,0 +1,347 @@
+// Backends for compressing a RecursiveSNARK into a CompressedSNARK, and persistence of their
+// prover and verifier keys.
+//
+// A key file is the magic `nspk` (prover key) or `nsvk` (verifier key), a little-endian u32
+// format version, the id of the backend the key was created for, and the bincode encoding of
+// the key.
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use nova_snark::{
+    nova::{CompressedSNARK, ProverKey, VerifierKey},
+    provider::{hyperkzg, ipa_pc, Bn256EngineKZG, GrumpkinEngine},
+    spartan::{ppsnark, snark},
+    traits::{evaluation::EvaluationEngineTrait, snark::RelaxedR1CSSNARKTrait, Engine},
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::error::NovaScotiaError;
+use crate::{C1, EE};
+
+/// A choice of SNARKs proving the primary and secondary folded instances of a CompressedSNARK.
+pub trait CompressionBackend<G1, G2>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+{
+    type S1: RelaxedR1CSSNARKTrait<G1>;
+    type S2: RelaxedR1CSSNARKTrait<G2>;
+
+    /// identifies the backend in key files
+    const ID: u8;
+    const NAME: &'static str;
+}
+
+/// Spartan with IPA polynomial commitments on both curves, the default [`crate::S`] SNARK
+pub struct Spartan;
+
+impl<G1, G2> CompressionBackend<G1, G2> for Spartan
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+    EE<G1>: EvaluationEngineTrait<G1>,
+    EE<G2>: EvaluationEngineTrait<G2>,
+{
+    type S1 = snark::RelaxedR1CSSNARK<G1, EE<G1>>;
+    type S2 = snark::RelaxedR1CSSNARK<G2, EE<G2>>;
+
+    const ID: u8 = 1;
+    const NAME: &'static str = "Spartan";
+}
+
+/// Preprocessing Spartan with IPA polynomial commitments on both curves.
+///
+/// Verification is faster than with [`Spartan`], but the public parameters need larger
+/// commitment keys, so they must be created with [`crate::create_public_params_for`].
+pub struct PreprocessingSpartan;
+
+impl<G1, G2> CompressionBackend<G1, G2> for PreprocessingSpartan
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+    EE<G1>: EvaluationEngineTrait<G1>,
+    EE<G2>: EvaluationEngineTrait<G2>,
+{
+    type S1 = ppsnark::RelaxedR1CSSNARK<G1, EE<G1>>;
+    type S2 = ppsnark::RelaxedR1CSSNARK<G2, EE<G2>>;
+
+    const ID: u8 = 2;
+    const NAME: &'static str = "preprocessing Spartan";
+}
+
+/// Spartan with HyperKZG commitments on BN254 and IPA on Grumpkin.
+///
+/// Nova derives the KZG structured reference string from a locally sampled secret when the
+/// public parameters are created, which is only suitable for testing.
+pub struct HyperKzg;
+
+impl CompressionBackend<Bn256EngineKZG, GrumpkinEngine> for HyperKzg {
+    type S1 = snark::RelaxedR1CSSNARK<Bn256EngineKZG, hyperkzg::EvaluationEngine<Bn256EngineKZG>>;
+    type S2 = snark::RelaxedR1CSSNARK<GrumpkinEngine, ipa_pc::EvaluationEngine<GrumpkinEngine>>;
+
+    const ID: u8 = 3;
+    const NAME: &'static str = "HyperKZG";
+}
+
+pub type CompressedSnark<G1, G2, B> = CompressedSNARK<
+    G1,
+    G2,
+    C1<G1>,
+    <B as CompressionBackend<G1, G2>>::S1,
+    <B as CompressionBackend<G1, G2>>::S2,
+>;
+pub type CompressionProverKey<G1, G2, B> = ProverKey<
+    G1,
+    G2,
+    C1<G1>,
+    <B as CompressionBackend<G1, G2>>::S1,
+    <B as CompressionBackend<G1, G2>>::S2,
+>;
+pub type CompressionVerifierKey<G1, G2, B> = VerifierKey<
+    G1,
+    G2,
+    C1<G1>,
+    <B as CompressionBackend<G1, G2>>::S1,
+    <B as CompressionBackend<G1, G2>>::S2,
+>;
+pub type CompressionKeys<G1, G2, B> = (
+    CompressionProverKey<G1, G2, B>,
+    CompressionVerifierKey<G1, G2, B>,
+);
+
+const PROVER_KEY_MAGIC: &[u8; 4] = b"nspk";
+const VERIFIER_KEY_MAGIC: &[u8; 4] = b"nsvk";
+const VERSION: u32 = 1;
+
+fn write_key<K: Serialize, W: Write>(
+    mut writer: W,
+    magic: &[u8; 4],
+    backend: u8,
+    key: &K,
+) -> Result<(), NovaScotiaError> {
+    writer.write_all(magic)?;
+    writer.write_u32::<LittleEndian>(VERSION)?;
+    writer.write_u8(backend)?;
+    bincode::serialize_into(writer, key)?;
+    Ok(())
+}
+
+fn read_key<K: DeserializeOwned, R: Read>(
+    mut reader: R,
+    magic: &[u8; 4],
+    kind: &str,
+    backend: u8,
+    backend_name: &str,
+) -> Result<K, NovaScotiaError> {
+    let mut found = [0u8; 4];
+    reader.read_exact(&mut found)?;
+    if &found != magic {
+        return Err(NovaScotiaError::InvalidInput(format!(
+            "not a Nova Scotia {} key file",
+            kind
+        )));
+    }
+    let version = reader.read_u32::<LittleEndian>()?;
+    if version != VERSION {
+        return Err(NovaScotiaError::InvalidInput(format!(
+            "unsupported key file version {}",
+            version
+        )));
+    }
+    let found = reader.read_u8()?;
+    if found != backend {
+        return Err(NovaScotiaError::InvalidInput(format!(
+            "{} key was created for compression backend {}, not {} ({})",
+            kind, found, backend_name, backend
+        )));
+    }
+    Ok(bincode::deserialize_from(reader)?)
+}
+
+/// write the prover key of backend `B` to `path`
+pub fn save_prover_key<G1, G2, B>(
+    pk: &CompressionProverKey<G1, G2, B>,
+    path: &Path,
+) -> Result<(), NovaScotiaError>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+    B: CompressionBackend<G1, G2>,
+{
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_key(&mut writer, PROVER_KEY_MAGIC, B::ID, pk)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// load a prover key of backend `B` written by [`save_prover_key`]
+pub fn load_prover_key<G1, G2, B>(
+    path: &Path,
+) -> Result<CompressionProverKey<G1, G2, B>, NovaScotiaError>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+    B: CompressionBackend<G1, G2>,
+{
+    read_key(
+        BufReader::new(File::open(path)?),
+        PROVER_KEY_MAGIC,
+        "prover",
+        B::ID,
+        B::NAME,
+    )
+}
+
+/// write the verifier key of backend `B` to `path`
+pub fn save_verifier_key<G1, G2, B>(
+    vk: &CompressionVerifierKey<G1, G2, B>,
+    path: &Path,
+) -> Result<(), NovaScotiaError>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+    B: CompressionBackend<G1, G2>,
+{
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_key(&mut writer, VERIFIER_KEY_MAGIC, B::ID, vk)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// load a verifier key of backend `B` written by [`save_verifier_key`]
+pub fn load_verifier_key<G1, G2, B>(
+    path: &Path,
+) -> Result<CompressionVerifierKey<G1, G2, B>, NovaScotiaError>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+    B: CompressionBackend<G1, G2>,
+{
+    read_verifier_key::<G1, G2, B, _>(BufReader::new(File::open(path)?))
+}
+
+/// read a verifier key of backend `B` written by [`save_verifier_key`] from a reader
+pub fn read_verifier_key<G1, G2, B, R: Read>(
+    reader: R,
+) -> Result<CompressionVerifierKey<G1, G2, B>, NovaScotiaError>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+    B: CompressionBackend<G1, G2>,
+{
+    read_key(reader, VERIFIER_KEY_MAGIC, "verifier", B::ID, B::NAME)
+}
+
+#[cfg(all(test, feature = "prover", feature = "verifier"))]
+mod tests {
+    use super::*;
+    use crate::bundle::ProofBundle;
+    use crate::circom::circuit::CircomCircuit;
+    use crate::circom::reader::load_r1cs;
+    use crate::verifier::Verifier;
+    use crate::{
+        compress, create_public_params_for, setup_compression, verify_compressed, FileLocation, F,
+    };
+    use ff::Field;
+    use nova_snark::nova::RecursiveSNARK;
+    use nova_snark::provider::{PallasEngine, VestaEngine};
+    use std::sync::Arc;
+
+    // fold three steps of the toy circuit from (10, 10), compress them with backend `B` and
+    // verify with keys that went through a file
+    fn compress_toy<G1, G2, B>(group_name: &str)
+    where
+        G1: Engine<Base = <G2 as Engine>::Scalar>,
+        G2: Engine<Base = <G1 as Engine>::Scalar>,
+        B: CompressionBackend<G1, G2>,
+    {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let r1cs = load_r1cs::<G1, G2>(&FileLocation::PathBuf(
+            root.join(format!("examples/toy/{}/toy.r1cs", group_name)),
+        ))
+        .unwrap();
+        let pp = create_public_params_for::<G1, G2, B>(r1cs.clone()).unwrap();
+
+        let r1cs = Arc::new(r1cs);
+        let z0 = vec![F::<G1>::from(10), F::<G1>::from(10)];
+        let mut z = z0.clone();
+        let mut recursive_snark: Option<RecursiveSNARK<G1, G2, C1<G1>>> = None;
+        for adder in 0..3u64 {
+            // step_out <== [step_in[0] + adder, step_in[0] + step_in[1]]
+            let witness = vec![
+                F::<G1>::ONE,
+                z[0] + F::<G1>::from(adder),
+                z[0] + z[1],
+                z[0],
+                z[1],
+            ];
+            let circuit = CircomCircuit {
+                r1cs: r1cs.clone(),
+                witness: Some(witness),
+            };
+            z = circuit.get_public_outputs();
+            recursive_snark
+                .get_or_insert_with(|| RecursiveSNARK::new(&pp, &circuit, &z0).unwrap())
+                .prove_step(&pp, &circuit)
+                .unwrap();
+        }
+        let recursive_snark = recursive_snark.unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let (pk, vk) = setup_compression::<G1, G2, B>(&pp).unwrap();
+        save_prover_key::<G1, G2, B>(&pk, &dir.path().join("pk")).unwrap();
+        save_verifier_key::<G1, G2, B>(&vk, &dir.path().join("vk")).unwrap();
+        let pk = load_prover_key::<G1, G2, B>(&dir.path().join("pk")).unwrap();
+        let vk = load_verifier_key::<G1, G2, B>(&dir.path().join("vk")).unwrap();
+
+        let compressed_snark = compress::<G1, G2, B>(&pp, &pk, &recursive_snark).unwrap();
+        let z_n = verify_compressed::<G1, G2, B>(&vk, &compressed_snark, 3, &z0).unwrap();
+        assert_eq!(z_n, vec![F::<G1>::from(13), F::<G1>::from(41)]);
+        assert!(verify_compressed::<G1, G2, B>(&vk, &compressed_snark, 2, &z0).is_err());
+
+        // the same proof as a bundle, checked with nothing but the verifier key
+        let verifier = Verifier::<G1, G2, B>::load(&dir.path().join("vk")).unwrap();
+        let path = dir.path().join("toy.compressed");
+        ProofBundle::compressed::<B>(&r1cs, &pp, &recursive_snark, &z0, &compressed_snark)
+            .unwrap()
+            .save(&path)
+            .unwrap();
+        let verified = verifier.verify_file(&path).unwrap();
+        assert_eq!(verified.num_steps, 3);
+        assert_eq!(verified.z0, z0);
+        assert_eq!(verified.zn, z_n);
+        let recursive = ProofBundle::recursive(&r1cs, &pp, &recursive_snark, &z0).unwrap();
+        assert!(verifier.verify(&recursive).is_err());
+    }
+
+    #[test]
+    fn test_spartan() {
+        compress_toy::<PallasEngine, VestaEngine, Spartan>("pasta");
+    }
+
+    #[test]
+    #[ignore = "compressing with preprocessing Spartan takes minutes, CI runs it in release mode"]
+    fn test_preprocessing_spartan() {
+        compress_toy::<PallasEngine, VestaEngine, PreprocessingSpartan>("pasta");
+    }
+
+    #[test]
+    fn test_hyperkzg() {
+        compress_toy::<Bn256EngineKZG, GrumpkinEngine, HyperKzg>("bn254");
+    }
+
+    #[test]
+    fn test_key_for_other_backend() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("vk");
+        let mut writer = File::create(&path).unwrap();
+        write_key(&mut writer, VERIFIER_KEY_MAGIC, 1, &()).unwrap();
+        let res = load_verifier_key::<PallasEngine, VestaEngine, PreprocessingSpartan>(&path);
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            "invalid input: verifier key was created for compression backend 1, not preprocessing Spartan (2)"
+        );
+    }
+}
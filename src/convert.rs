@@ -0,0 +1,323 @@
+// Conversions between field elements and the integers circom reads and writes.
+//
+// Circom takes signal values as decimal strings (or hex, or negative numbers, reduced into the
+// field), nested in JSON arrays for array signals, and writes them back as decimal strings.
+#[cfg(feature = "prover")]
+use std::fmt;
+
+use ff::PrimeField;
+use num_bigint::BigUint;
+use num_traits::Num;
+#[cfg(feature = "prover")]
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+#[cfg(feature = "prover")]
+use serde_json::Value;
+
+use crate::error::NovaScotiaError;
+
+// `PrimeField` leaves the byte order of `Repr` to the field, so look at how it encodes 1
+fn repr_is_little_endian<Fr: PrimeField>() -> bool {
+    Fr::ONE.to_repr().as_ref()[0] == 1
+}
+
+/// the canonical integer of a field element
+pub fn to_biguint<Fr: PrimeField>(value: &Fr) -> BigUint {
+    let repr = value.to_repr();
+    if repr_is_little_endian::<Fr>() {
+        BigUint::from_bytes_le(repr.as_ref())
+    } else {
+        BigUint::from_bytes_be(repr.as_ref())
+    }
+}
+
+/// the field element of an integer below the modulus
+pub fn from_biguint<Fr: PrimeField>(value: &BigUint) -> Result<Fr, NovaScotiaError> {
+    let mut repr = Fr::ZERO.to_repr();
+    let bytes = value.to_bytes_le();
+    let len = repr.as_ref().len();
+    if bytes.len() > len {
+        return Err(NovaScotiaError::NonCanonicalFieldElement(format!(
+            "{} does not fit in the target field",
+            value
+        )));
+    }
+    if repr_is_little_endian::<Fr>() {
+        repr.as_mut()[..bytes.len()].copy_from_slice(&bytes);
+    } else {
+        for (i, byte) in bytes.iter().enumerate() {
+            repr.as_mut()[len - 1 - i] = *byte;
+        }
+    }
+    Option::from(Fr::from_repr(repr)).ok_or_else(|| {
+        NovaScotiaError::NonCanonicalFieldElement(format!(
+            "{} is not canonical in the target field",
+            value
+        ))
+    })
+}
+
+/// the decimal string circom uses for a field element
+pub fn to_decimal<Fr: PrimeField>(value: &Fr) -> String {
+    to_biguint(value).to_str_radix(10)
+}
+
+/// the lowercase hex string of a byte string, as digests are printed
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// parse the canonical decimal form of a field element, as circom and snarkjs write them: only
+/// digits, no leading zeros, and below the modulus
+pub fn from_decimal<Fr: PrimeField>(value: &str) -> Result<Fr, NovaScotiaError> {
+    if value.is_empty()
+        || !value.bytes().all(|byte| byte.is_ascii_digit())
+        || (value.len() > 1 && value.starts_with('0'))
+    {
+        return Err(NovaScotiaError::NonCanonicalFieldElement(format!(
+            "{:?} is not a decimal number",
+            value
+        )));
+    }
+    let value = BigUint::from_str_radix(value, 10).expect("checked to be decimal digits");
+    from_biguint(&value)
+}
+
+/// parse a field element written as a decimal or `0x`-prefixed hex integer, optionally negative
+///
+/// Negative values are negated in the field, so `"-1"` is the modulus minus one. Magnitudes must
+/// be below the modulus.
+pub fn parse<Fr: PrimeField>(value: &str) -> Result<Fr, NovaScotiaError> {
+    let invalid = || NovaScotiaError::InvalidInput(format!("invalid field element {:?}", value));
+    let (negative, magnitude) = match value.strip_prefix('-') {
+        Some(magnitude) => (true, magnitude),
+        None => (false, value),
+    };
+    let (radix, digits) = match magnitude
+        .strip_prefix("0x")
+        .or_else(|| magnitude.strip_prefix("0X"))
+    {
+        Some(hex) => (16, hex),
+        None => (10, magnitude),
+    };
+    if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+        return Err(invalid());
+    }
+    let magnitude = BigUint::from_str_radix(digits, radix).map_err(|_| invalid())?;
+    let element = from_biguint::<Fr>(&magnitude)?;
+    Ok(if negative { -element } else { element })
+}
+
+#[cfg(feature = "prover")]
+/// the field elements of a circom input value, flattening nested arrays in row-major order
+///
+/// Leaves may be integers, or strings accepted by [`parse`].
+pub fn from_json<Fr: PrimeField>(value: &Value) -> Result<Vec<Fr>, NovaScotiaError> {
+    let mut elements = vec![];
+    push_json(value, &mut elements)?;
+    Ok(elements)
+}
+
+#[cfg(feature = "prover")]
+fn push_json<Fr: PrimeField>(value: &Value, out: &mut Vec<Fr>) -> Result<(), NovaScotiaError> {
+    match value {
+        Value::Array(values) => values.iter().try_for_each(|value| push_json(value, out)),
+        Value::String(s) => {
+            out.push(parse(s)?);
+            Ok(())
+        }
+        Value::Number(n) if n.is_u64() || n.is_i64() => {
+            out.push(parse(&n.to_string())?);
+            Ok(())
+        }
+        _ => Err(NovaScotiaError::InvalidInput(format!(
+            "{} is not a field element",
+            value
+        ))),
+    }
+}
+
+#[cfg(feature = "prover")]
+/// a JSON array of the decimal strings of `values`, ready to be a circom input
+pub fn to_json<Fr: PrimeField>(values: &[Fr]) -> Value {
+    Value::Array(
+        values
+            .iter()
+            .map(|value| Value::String(to_decimal(value)))
+            .collect(),
+    )
+}
+
+#[cfg(feature = "prover")]
+/// A field element that serializes as its decimal string, so it can go straight into circom
+/// inputs: `json!({ "root": Decimal(root), "path": path.map(Decimal) })`.
+///
+/// It deserializes from anything [`from_json`] reads as a single element.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Decimal<Fr>(pub Fr);
+
+#[cfg(feature = "prover")]
+impl<Fr: PrimeField> Serialize for Decimal<Fr> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&to_decimal(&self.0))
+    }
+}
+
+#[cfg(feature = "prover")]
+impl<'de, Fr: PrimeField> Deserialize<'de> for Decimal<Fr> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        let elements = from_json::<Fr>(&value).map_err(de::Error::custom)?;
+        match elements.as_slice() {
+            [element] if !value.is_array() => Ok(Decimal(*element)),
+            _ => Err(de::Error::custom(format!(
+                "{} is not a single field element",
+                value
+            ))),
+        }
+    }
+}
+
+#[cfg(feature = "prover")]
+impl<Fr: PrimeField> fmt::Display for Decimal<Fr> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&to_decimal(&self.0))
+    }
+}
+
+#[cfg(feature = "prover")]
+/// serde adapter for a field element in circom's decimal form, for
+/// `#[serde(with = "nova_scotia::convert::decimal")]` on a field of private inputs
+pub mod decimal {
+    use super::*;
+
+    pub fn serialize<Fr: PrimeField, S: Serializer>(
+        value: &Fr,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        Decimal(*value).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, Fr: PrimeField, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Fr, D::Error> {
+        Ok(Decimal::deserialize(deserializer)?.0)
+    }
+}
+
+#[cfg(feature = "prover")]
+/// serde adapter for a `Vec` of field elements in circom's decimal form, for
+/// `#[serde(with = "nova_scotia::convert::decimal_vec")]`; nested arrays are flattened when
+/// deserializing
+pub mod decimal_vec {
+    use super::*;
+
+    pub fn serialize<Fr: PrimeField, S: Serializer>(
+        values: &[Fr],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        to_json(values).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, Fr: PrimeField, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Fr>, D::Error> {
+        from_json(&Value::deserialize(deserializer)?).map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circom::prime::modulus;
+    use ff::Field;
+    #[cfg(feature = "prover")]
+    use serde_json::json;
+
+    type Fr = <nova_snark::provider::PallasEngine as nova_snark::traits::Engine>::Scalar;
+    type Bn = <nova_snark::provider::Bn256EngineIPA as nova_snark::traits::Engine>::Scalar;
+
+    #[test]
+    fn test_to_hex() {
+        assert_eq!(to_hex(&[]), "");
+        assert_eq!(to_hex(&[0x00, 0x0f, 0xab, 0xff]), "000fabff");
+    }
+
+    #[test]
+    fn test_decimal_round_trip() {
+        for value in [
+            Fr::ZERO,
+            Fr::from(42),
+            -Fr::ONE,
+            Fr::from(u64::MAX).square(),
+        ] {
+            assert_eq!(from_decimal::<Fr>(&to_decimal(&value)).unwrap(), value);
+            assert_eq!(parse::<Fr>(&to_decimal(&value)).unwrap(), value);
+        }
+        assert_eq!(to_decimal(&Bn::from(1234)), "1234");
+        assert_eq!(
+            to_decimal(&-Fr::ONE),
+            (modulus::<Fr>() - 1u32).to_str_radix(10)
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse::<Fr>("0x2a").unwrap(), Fr::from(42));
+        assert_eq!(parse::<Fr>("0X2A").unwrap(), Fr::from(42));
+        assert_eq!(parse::<Fr>("-5").unwrap(), -Fr::from(5));
+        assert_eq!(parse::<Fr>("-0x5").unwrap(), -Fr::from(5));
+        assert_eq!(parse::<Fr>("007").unwrap(), Fr::from(7));
+        for invalid in ["", "-", "0x", "1.5", "+1", "1_000", "0xg"] {
+            assert!(
+                matches!(parse::<Fr>(invalid), Err(NovaScotiaError::InvalidInput(_))),
+                "{:?}",
+                invalid
+            );
+        }
+        assert!(matches!(
+            parse::<Fr>(&modulus::<Fr>().to_str_radix(10)),
+            Err(NovaScotiaError::NonCanonicalFieldElement(_))
+        ));
+        assert!(from_decimal::<Fr>("007").is_err());
+    }
+
+    #[cfg(feature = "prover")]
+    #[test]
+    fn test_json() {
+        let value = json!([[1, "2"], ["0x3", -4]]);
+        assert_eq!(
+            from_json::<Fr>(&value).unwrap(),
+            vec![Fr::from(1), Fr::from(2), Fr::from(3), -Fr::from(4)]
+        );
+        assert!(from_json::<Fr>(&json!([1.5])).is_err());
+        assert!(from_json::<Fr>(&json!({"a": 1})).is_err());
+        assert_eq!(to_json(&[Fr::from(1), -Fr::ONE])[0], json!("1"));
+
+        #[derive(Serialize, Deserialize)]
+        struct Inputs {
+            #[serde(with = "decimal")]
+            root: Fr,
+            #[serde(with = "decimal_vec")]
+            path: Vec<Fr>,
+        }
+        let inputs = Inputs {
+            root: Fr::from(7),
+            path: vec![Fr::from(8), -Fr::ONE],
+        };
+        let value = serde_json::to_value(&inputs).unwrap();
+        assert_eq!(value["root"], json!("7"));
+        assert_eq!(value["path"][1], json!(to_decimal(&-Fr::ONE)));
+        let inputs: Inputs = serde_json::from_value(value).unwrap();
+        assert_eq!(inputs.path, vec![Fr::from(8), -Fr::ONE]);
+
+        assert_eq!(
+            json!({ "x": Decimal(Fr::from(3)), "xs": [Decimal(Fr::from(4))] }),
+            json!({ "x": "3", "xs": ["4"] })
+        );
+        assert_eq!(
+            serde_json::from_value::<Decimal<Fr>>(json!("-1")).unwrap(),
+            Decimal(-Fr::ONE)
+        );
+        assert!(serde_json::from_value::<Decimal<Fr>>(json!(["1"])).is_err());
+    }
+}
@@ -0,0 +1,100 @@
+#[cfg(feature = "supernova")]
+use arecibo::supernova::error::SuperNovaError;
+use nova_snark::errors::NovaError;
+use thiserror::Error;
+
+/// Errors returned by Nova Scotia
+#[derive(Debug, Error)]
+pub enum NovaScotiaError {
+    /// reading or writing a file failed
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// an R1CS file is truncated or one of its sections is malformed
+    #[error("malformed R1CS file: {0}")]
+    MalformedR1CS(String),
+
+    /// a witness file is truncated or one of its sections is malformed
+    #[error("malformed witness file: {0}")]
+    MalformedWitness(String),
+
+    /// a file was produced for a different field than the one requested
+    #[error("field mismatch: expected {expected}, found {found}")]
+    FieldMismatch { expected: String, found: String },
+
+    /// a field element is not in canonical form for the target field
+    #[error("non-canonical field element: {0}")]
+    NonCanonicalFieldElement(String),
+
+    /// the external witness generator exited unsuccessfully
+    #[error("witness generator failed with {}\nstdout: {stdout}\nstderr: {stderr}", match .status {
+        Some(code) => format!("exit status {}", code),
+        None => "no exit status".to_string(),
+    })]
+    WitnessGenerator {
+        status: Option<i32>,
+        stdout: String,
+        stderr: String,
+    },
+
+    /// the in-process WASM witness calculator failed
+    #[error("WASM witness calculation failed: {0}")]
+    Wasm(String),
+
+    /// an R1CS file applies PLONK custom gates, which cannot be folded as R1CS
+    #[error("circuit uses custom gates, which Nova Scotia cannot fold: {0}")]
+    UnsupportedCustomGates(String),
+
+    /// the inputs handed to Nova Scotia are invalid
+    #[error("invalid input: {0}")]
+    InvalidInput(String),
+
+    /// serializing or deserializing JSON failed
+    #[cfg(feature = "prover")]
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    /// encoding or decoding a binary artifact failed
+    #[error("serialization error: {0}")]
+    Serialization(#[from] bincode::Error),
+
+    /// an artifact was produced for different public parameters or a different circuit
+    #[error("digest mismatch: {0}")]
+    DigestMismatch(String),
+
+    /// a witness does not satisfy the constraints of its circuit
+    #[error("witness does not satisfy {count} constraints:\n{report}")]
+    UnsatisfiedWitness { count: usize, report: String },
+
+    /// Nova rejected the circuit while generating public parameters
+    #[error("Nova error: {0}")]
+    Nova(#[from] NovaError),
+
+    /// Nova failed to fold a step
+    #[error("Nova prover failed at step {step}: {source}")]
+    Prover { step: usize, source: NovaError },
+
+    /// SuperNova rejected a non-uniform proof
+    #[cfg(feature = "supernova")]
+    #[error("SuperNova error: {0:?}")]
+    SuperNova(#[from] SuperNovaError),
+
+    /// SuperNova failed to fold a step of a non-uniform program
+    #[cfg(feature = "supernova")]
+    #[error("SuperNova prover failed at step {step}: {source:?}")]
+    SuperNovaProver { step: usize, source: SuperNovaError },
+}
+
+#[cfg(feature = "prover")]
+impl From<wasmi::Error> for NovaScotiaError {
+    fn from(err: wasmi::Error) -> Self {
+        NovaScotiaError::Wasm(err.to_string())
+    }
+}
+
+#[cfg(feature = "prover")]
+impl From<wasmi::errors::LinkerError> for NovaScotiaError {
+    fn from(err: wasmi::errors::LinkerError) -> Self {
+        NovaScotiaError::Wasm(err.to_string())
+    }
+}
@@ -0,0 +1,266 @@
+// Sources of per-step private inputs that the prover pulls from one step at a time.
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+#[cfg(feature = "stream")]
+use std::{
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll, Wake, Waker},
+    thread::{self, Thread},
+};
+
+#[cfg(feature = "stream")]
+use futures_core::Stream;
+use serde_json::Value;
+
+use crate::error::NovaScotiaError;
+
+/// The private inputs of one step, as handed to the witness generator next to `step_in`.
+pub type StepInput = HashMap<String, Value>;
+
+/// Supplies the private inputs of each step while folding, so the number of steps need not be
+/// known up front.
+///
+/// Any iterator over [`StepInput`] is a source, including the blocking iterator of an
+/// `mpsc::Receiver`, which folds new inputs as they arrive and stops when every sender is gone.
+/// With the `stream` feature, [`StreamInputs`] turns an async `Stream` into a source.
+pub trait StepInputSource<Fr> {
+    /// the private inputs of step `step`, or `None` to end the run
+    ///
+    /// Steps are asked for in order, each once the previous step has been folded. `step` counts
+    /// the steps of the whole RecursiveSNARK from 0, so a run resumed from a checkpoint starts at
+    /// the number of steps already folded. `z_i` is the step's `step_in`: the `start_public_input`
+    /// for step 0, the `step_out` of step `step - 1` after that. After `None` the source is not
+    /// asked again and the steps folded so far are the result; an error aborts the run.
+    fn next_input(&mut self, step: usize, z_i: &[Fr])
+        -> Result<Option<StepInput>, NovaScotiaError>;
+}
+
+impl<Fr, I> StepInputSource<Fr> for I
+where
+    I: Iterator<Item = StepInput>,
+{
+    fn next_input(
+        &mut self,
+        _step: usize,
+        _z_i: &[Fr],
+    ) -> Result<Option<StepInput>, NovaScotiaError> {
+        Ok(self.next())
+    }
+}
+
+/// Reads one JSON object of private inputs per line, skipping blank lines, as late as possible.
+pub struct JsonlInputs<R> {
+    reader: R,
+    line: String,
+}
+
+impl<R: BufRead> JsonlInputs<R> {
+    pub fn new(reader: R) -> Self {
+        JsonlInputs {
+            reader,
+            line: String::new(),
+        }
+    }
+}
+
+impl<R: BufRead> JsonlInputs<R> {
+    /// skip the private inputs of the first `steps` steps, e.g. those a checkpoint already folded
+    pub fn skip(&mut self, steps: usize) -> Result<(), NovaScotiaError> {
+        for step in 0..steps {
+            if self.next_line(step)?.is_none() {
+                return Err(NovaScotiaError::InvalidInput(format!(
+                    "cannot skip {} steps, only {} private inputs were provided",
+                    steps, step
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn next_line(&mut self, step: usize) -> Result<Option<StepInput>, NovaScotiaError> {
+        loop {
+            self.line.clear();
+            if self.reader.read_line(&mut self.line)? == 0 {
+                return Ok(None);
+            }
+            if !self.line.trim().is_empty() {
+                return serde_json::from_str(&self.line).map(Some).map_err(|err| {
+                    NovaScotiaError::InvalidInput(format!(
+                        "private inputs of step {}: {}",
+                        step, err
+                    ))
+                });
+            }
+        }
+    }
+}
+
+impl JsonlInputs<BufReader<File>> {
+    pub fn open(path: &Path) -> Result<Self, NovaScotiaError> {
+        Ok(Self::new(BufReader::new(File::open(path)?)))
+    }
+}
+
+impl<Fr, R: BufRead> StepInputSource<Fr> for JsonlInputs<R> {
+    fn next_input(
+        &mut self,
+        step: usize,
+        _z_i: &[Fr],
+    ) -> Result<Option<StepInput>, NovaScotiaError> {
+        self.next_line(step)
+    }
+}
+
+/// A source calling `f(step, z_i)` for the private inputs of each step, see [`from_fn`].
+pub struct FromFn<F>(F);
+
+/// Build a source from a callback that sees each step's `step_in`, for circuits whose next inputs
+/// depend on the state folded so far.
+pub fn from_fn<Fr, F>(f: F) -> FromFn<F>
+where
+    F: FnMut(usize, &[Fr]) -> Result<Option<StepInput>, NovaScotiaError>,
+{
+    FromFn(f)
+}
+
+impl<Fr, F> StepInputSource<Fr> for FromFn<F>
+where
+    F: FnMut(usize, &[Fr]) -> Result<Option<StepInput>, NovaScotiaError>,
+{
+    fn next_input(
+        &mut self,
+        step: usize,
+        z_i: &[Fr],
+    ) -> Result<Option<StepInput>, NovaScotiaError> {
+        (self.0)(step, z_i)
+    }
+}
+
+/// A source pulling the private inputs of each step from a [`Stream`], for inputs produced by
+/// async code. The run ends when the stream does.
+///
+/// `next_input` blocks the proving thread until the stream yields, without an async runtime, so
+/// the stream must make progress on its own, like the receiving half of an async channel whose
+/// sender lives on another runtime or thread.
+#[cfg(feature = "stream")]
+pub struct StreamInputs<S>(S);
+
+#[cfg(feature = "stream")]
+impl<S: Stream<Item = StepInput> + Unpin> StreamInputs<S> {
+    pub fn new(stream: S) -> Self {
+        StreamInputs(stream)
+    }
+}
+
+// wakes the proving thread parked in `StreamInputs::next_input`
+#[cfg(feature = "stream")]
+struct ThreadWaker(Thread);
+
+#[cfg(feature = "stream")]
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<Fr, S: Stream<Item = StepInput> + Unpin> StepInputSource<Fr> for StreamInputs<S> {
+    fn next_input(
+        &mut self,
+        _step: usize,
+        _z_i: &[Fr],
+    ) -> Result<Option<StepInput>, NovaScotiaError> {
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            match Pin::new(&mut self.0).poll_next(&mut cx) {
+                Poll::Ready(input) => return Ok(input),
+                // a spurious unpark only costs another poll
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_jsonl_inputs() {
+        let jsonl = "{\"adder\": 0}\n\n  \n{\"adder\": 1}\n{\"adder\"\n";
+        let mut source = JsonlInputs::new(jsonl.as_bytes());
+        for adder in 0..2 {
+            let input = StepInputSource::<u64>::next_input(&mut source, adder, &[]).unwrap();
+            assert_eq!(input.unwrap()["adder"], json!(adder));
+        }
+        let err = StepInputSource::<u64>::next_input(&mut source, 2, &[])
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("step 2"), "{}", err);
+        assert!(StepInputSource::<u64>::next_input(&mut source, 3, &[])
+            .unwrap()
+            .is_none());
+
+        let mut source = JsonlInputs::new(jsonl.as_bytes());
+        source.skip(1).unwrap();
+        let input = StepInputSource::<u64>::next_input(&mut source, 1, &[]).unwrap();
+        assert_eq!(input.unwrap()["adder"], json!(1));
+        let err = JsonlInputs::new(jsonl.as_bytes()).skip(4).err().unwrap();
+        assert!(err.to_string().contains("step 2"), "{}", err);
+        let err = JsonlInputs::new("{}\n".as_bytes()).skip(2).err().unwrap();
+        assert!(err.to_string().contains("only 1"), "{}", err);
+    }
+
+    // yields `adder` 0, 1, 2, each only after another thread has woken it
+    #[cfg(feature = "stream")]
+    struct DelayedStream {
+        next: u64,
+        ready: Arc<std::sync::atomic::AtomicBool>,
+    }
+
+    #[cfg(feature = "stream")]
+    impl Stream for DelayedStream {
+        type Item = StepInput;
+
+        fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<StepInput>> {
+            use std::sync::atomic::Ordering;
+
+            if self.next == 3 {
+                return Poll::Ready(None);
+            }
+            if !self.ready.swap(false, Ordering::SeqCst) {
+                let (ready, waker) = (self.ready.clone(), cx.waker().clone());
+                thread::spawn(move || {
+                    thread::sleep(std::time::Duration::from_millis(10));
+                    ready.store(true, Ordering::SeqCst);
+                    waker.wake();
+                });
+                return Poll::Pending;
+            }
+            let input = HashMap::from([("adder".to_string(), json!(self.next))]);
+            self.next += 1;
+            Poll::Ready(Some(input))
+        }
+    }
+
+    #[cfg(feature = "stream")]
+    #[test]
+    fn test_stream_inputs() {
+        let mut source = StreamInputs::new(DelayedStream {
+            next: 0,
+            ready: Default::default(),
+        });
+        for adder in 0..3 {
+            let input = StepInputSource::<u64>::next_input(&mut source, adder, &[]).unwrap();
+            assert_eq!(input.unwrap()["adder"], json!(adder));
+        }
+        assert!(StepInputSource::<u64>::next_input(&mut source, 3, &[])
+            .unwrap()
+            .is_none());
+    }
+}
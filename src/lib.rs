@@ -1,23 +1,52 @@
+#[cfg(feature = "prover")]
 use std::{
-    collections::HashMap,
-    env::current_dir,
-    fs,
+    collections::{BTreeMap, HashMap},
+    io::{BufWriter, Write},
     path::{Path, PathBuf},
+    sync::{mpsc, Arc, Mutex},
+    thread,
 };
 
-use crate::circom::reader::generate_witness_from_bin;
-use circom::circuit::{CircomCircuit, R1CS};
-use ff::Field;
+#[cfg(feature = "prover")]
+use crate::{
+    checkpoint::{save_checkpoint, Checkpoint},
+    circom::{circuit::R1CS, reader::generate_witness_from_bin, wasm::WitnessCalculator},
+    compression::{CompressionKeys, CompressionProverKey},
+    inputs::StepInputSource,
+};
+use circom::circuit::CircomCircuit;
+use compression::{CompressedSnark, CompressionBackend, CompressionVerifierKey};
+pub use error::NovaScotiaError;
+#[cfg(feature = "prover")]
+use ff::PrimeField;
+use nova_snark::traits::{circuit::TrivialCircuit, Engine};
+#[cfg(feature = "prover")]
 use nova_snark::{
-    traits::{circuit::TrivialCircuit, Engine},
-    nova::{PublicParams, RecursiveSNARK},
+    nova::{CompressedSNARK, PublicParams, RecursiveSNARK},
+    traits::snark::RelaxedR1CSSNARKTrait,
 };
-use num_bigint::BigInt;
-use num_traits::Num;
-use serde::{Deserialize, Serialize};
+#[cfg(feature = "prover")]
 use serde_json::Value;
+#[cfg(feature = "supernova")]
+use {
+    arecibo::{supernova::RecursiveSNARK as SuperNovaSNARK, traits::Engine as SuperNovaEngine},
+    circom::nonuniform::{NonUniformProgram, NonUniformPublicParams, ProgramRun, SecondaryCircuit},
+    ff::Field,
+};
 
+pub mod bundle;
+#[cfg(feature = "prover")]
+pub mod checkpoint;
 pub mod circom;
+pub mod compression;
+pub mod convert;
+pub mod error;
+#[cfg(feature = "prover")]
+pub mod inputs;
+#[cfg(feature = "prover")]
+pub mod params;
+#[cfg(feature = "verifier")]
+pub mod verifier;
 
 pub type F<G> = <G as Engine>::Scalar;
 pub type EE<G> = nova_snark::provider::ipa_pc::EvaluationEngine<G>;
@@ -25,19 +54,42 @@ pub type S<G> = nova_snark::spartan::snark::RelaxedR1CSSNARK<G, EE<G>>;
 pub type C1<G> = CircomCircuit<<G as Engine>::Scalar>;
 pub type C2<G> = TrivialCircuit<<G as Engine>::Scalar>;
 
+#[cfg(feature = "prover")]
 #[derive(Clone)]
 pub enum FileLocation {
     PathBuf(PathBuf),
     URL(String),
 }
 
-pub fn create_public_params<G1, G2>(r1cs: R1CS<F<G1>>) -> Result<PublicParams<G1, G2, C1<G1>>, Box<dyn std::error::Error>>
+// write a file with `write` and replace `path` with it atomically, so that a crash while saving
+// leaves the previous file in place rather than a truncated one
+#[cfg(feature = "prover")]
+pub(crate) fn write_atomically(
+    path: &Path,
+    write: impl FnOnce(&mut dyn Write) -> Result<(), NovaScotiaError>,
+) -> Result<(), NovaScotiaError> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let mut writer = BufWriter::new(tempfile::NamedTempFile::new_in(dir)?);
+    write(&mut writer)?;
+    let file = writer.into_inner().map_err(|err| err.into_error())?;
+    file.as_file().sync_all()?;
+    file.persist(path).map_err(|err| err.error)?;
+    Ok(())
+}
+
+#[cfg(feature = "prover")]
+pub fn create_public_params<G1, G2>(
+    r1cs: R1CS<F<G1>>,
+) -> Result<PublicParams<G1, G2, C1<G1>>, NovaScotiaError>
 where
     G1: Engine<Base = <G2 as Engine>::Scalar>,
     G2: Engine<Base = <G1 as Engine>::Scalar>,
 {
     let circuit_primary = CircomCircuit {
-        r1cs,
+        r1cs: Arc::new(r1cs),
         witness: None,
     };
 
@@ -45,173 +97,1286 @@ where
     Ok(params)
 }
 
-#[derive(Serialize, Deserialize)]
-struct CircomInput {
-    step_in: Vec<String>,
+/// Create public parameters with commitment keys large enough for compression backend `B`.
+#[cfg(feature = "prover")]
+pub fn create_public_params_for<G1, G2, B>(
+    r1cs: R1CS<F<G1>>,
+) -> Result<PublicParams<G1, G2, C1<G1>>, NovaScotiaError>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+    B: CompressionBackend<G1, G2>,
+{
+    let circuit_primary = CircomCircuit {
+        r1cs: Arc::new(r1cs),
+        witness: None,
+    };
+
+    let params = PublicParams::setup(&circuit_primary, &*B::S1::ck_floor(), &*B::S2::ck_floor())?;
+    Ok(params)
+}
+
+/// Create the prover and verifier keys of compression backend `B`.
+#[cfg(feature = "prover")]
+pub fn setup_compression<G1, G2, B>(
+    pp: &PublicParams<G1, G2, C1<G1>>,
+) -> Result<CompressionKeys<G1, G2, B>, NovaScotiaError>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+    B: CompressionBackend<G1, G2>,
+{
+    Ok(CompressedSNARK::setup(pp)?)
+}
+
+/// Compress a RecursiveSNARK with backend `B`.
+#[cfg(feature = "prover")]
+pub fn compress<G1, G2, B>(
+    pp: &PublicParams<G1, G2, C1<G1>>,
+    pk: &CompressionProverKey<G1, G2, B>,
+    recursive_snark: &RecursiveSNARK<G1, G2, C1<G1>>,
+) -> Result<CompressedSnark<G1, G2, B>, NovaScotiaError>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+    B: CompressionBackend<G1, G2>,
+{
+    Ok(CompressedSNARK::prove(pp, pk, recursive_snark)?)
+}
+
+/// Verify a CompressedSNARK of `num_steps` steps from `z0`, returning `z_n`.
+pub fn verify_compressed<G1, G2, B>(
+    vk: &CompressionVerifierKey<G1, G2, B>,
+    compressed_snark: &CompressedSnark<G1, G2, B>,
+    num_steps: usize,
+    z0: &[F<G1>],
+) -> Result<Vec<F<G1>>, NovaScotiaError>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+    B: CompressionBackend<G1, G2>,
+{
+    Ok(compressed_snark.verify(vk, num_steps, z0)?)
+}
+
+// A witness generator that is loaded once and then run for every step: a `circuit.wasm` is
+// compiled and instantiated once, anything else is a C++ binary that is spawned per step.
+#[cfg(feature = "prover")]
+enum WitnessGenerator {
+    Wasm(Box<WitnessCalculator>),
+    Bin(PathBuf),
+}
+
+#[cfg(feature = "prover")]
+impl WitnessGenerator {
+    fn load(witness_generator_file: &FileLocation) -> Result<Self, NovaScotiaError> {
+        let path = match witness_generator_file {
+            FileLocation::PathBuf(path) => path,
+            FileLocation::URL(url) => {
+                return Err(NovaScotiaError::InvalidInput(format!(
+                    "URL-based witness generators are not supported: {}",
+                    url
+                )))
+            }
+        };
 
-    #[serde(flatten)]
-    extra: HashMap<String, Value>,
+        // `circuit.wasm` output of circom is run in-process, anything else is a C++ binary
+        if path.extension().is_some_and(|ext| ext == "wasm") {
+            Ok(WitnessGenerator::Wasm(Box::new(
+                WitnessCalculator::from_file(path)?,
+            )))
+        } else {
+            Ok(WitnessGenerator::Bin(path.clone()))
+        }
+    }
 }
 
-fn compute_witness<G1, G2>(
-    current_public_input: Vec<String>,
+#[cfg(feature = "prover")]
+fn compute_witness<Fr: PrimeField>(
+    current_public_input: &[Fr],
     private_input: HashMap<String, Value>,
-    witness_generator_file: FileLocation,
-    witness_generator_output: &Path,
-) -> Vec<<G1 as Engine>::Scalar>
+    witness_generator: &mut WitnessGenerator,
+) -> Result<Vec<Fr>, NovaScotiaError> {
+    let input = circom_input(current_public_input, private_input);
+    match witness_generator {
+        WitnessGenerator::Wasm(calculator) => calculator.calculate_witness(&input),
+        WitnessGenerator::Bin(path) => {
+            generate_witness_from_bin::<Fr>(path, &serde_json::to_string(&input)?)
+        }
+    }
+}
+
+// `compute_witness` for the pipelined prover, which hands the values at `step_out` to `publish`
+// as soon as they are known: a WASM calculator reads them before extracting the rest of the
+// witness, so the next step can start in the meantime
+#[cfg(feature = "prover")]
+fn compute_witness_ahead<G1, G2>(
+    current_public_input: &[F<G1>],
+    private_input: HashMap<String, Value>,
+    witness_generator: &mut WitnessGenerator,
+    step_out: &[usize],
+    publish: impl FnOnce(Vec<F<G1>>),
+) -> Result<Vec<<G1 as Engine>::Scalar>, NovaScotiaError>
 where
     G1: Engine<Base = <G2 as Engine>::Scalar>,
     G2: Engine<Base = <G1 as Engine>::Scalar>,
 {
-    let decimal_stringified_input: Vec<String> = current_public_input
-        .iter()
-        .map(|x| BigInt::from_str_radix(x, 16).unwrap().to_str_radix(10))
-        .collect();
+    match witness_generator {
+        WitnessGenerator::Wasm(calculator) => {
+            calculator.compute::<F<G1>>(&circom_input(current_public_input, private_input))?;
+            publish(calculator.witness_values(step_out.iter().copied())?);
+            calculator.witness_values(0..calculator.witness_size())
+        }
+        WitnessGenerator::Bin(_) => {
+            let witness = compute_witness(current_public_input, private_input, witness_generator)?;
+            publish(step_out.iter().map(|&i| witness[i]).collect());
+            Ok(witness)
+        }
+    }
+}
 
-    let input = CircomInput {
-        step_in: decimal_stringified_input.clone(),
-        extra: private_input.clone(),
-    };
+#[cfg(feature = "prover")]
+fn circom_input<Fr: PrimeField>(
+    current_public_input: &[Fr],
+    private_input: HashMap<String, Value>,
+) -> HashMap<String, Value> {
+    let mut input = private_input;
+    input.insert(
+        "step_in".to_string(),
+        convert::to_json(current_public_input),
+    );
+    input
+}
 
-    let input_json = serde_json::to_string(&input).unwrap();
-    
-    let witness_generator_file = match &witness_generator_file {
-        FileLocation::PathBuf(path) => path,
-        FileLocation::URL(_) => panic!("URL-based witness generators are not supported without WASM"),
-    };
-    
-    generate_witness_from_bin::<F<G1>>(
-        &witness_generator_file,
-        &input_json,
-        &witness_generator_output,
-    )
+/// A folded [`RecursiveSNARK`] together with the public values of its steps, so they can be read
+/// without verifying the snark.
+#[cfg(feature = "prover")]
+pub struct RecursiveOutput<G1, G2>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+{
+    pub recursive_snark: RecursiveSNARK<G1, G2, C1<G1>>,
+    pub num_steps: usize,
+    /// `step_in` of the first step
+    pub z0: Vec<F<G1>>,
+    /// `step_out` of the last step, the outputs `RecursiveSNARK::verify` returns
+    pub zn: Vec<F<G1>>,
+    /// `z_0, z_1, ..., z_n`, the `step_in` of every step followed by `z_n`, if it was recorded by
+    /// [`create_recursive_circuit_with_trace`]
+    pub trace: Option<Vec<Vec<F<G1>>>>,
 }
 
+#[cfg(feature = "prover")]
 pub fn create_recursive_circuit<G1, G2>(
     witness_generator_file: FileLocation,
     r1cs: R1CS<F<G1>>,
     private_inputs: Vec<HashMap<String, Value>>,
     start_public_input: Vec<F<G1>>,
     pp: &PublicParams<G1, G2, C1<G1>>,
-) -> Result<RecursiveSNARK<G1, G2, C1<G1>>, Box<dyn std::error::Error>>
+) -> Result<RecursiveOutput<G1, G2>, NovaScotiaError>
 where
     G1: Engine<Base = <G2 as Engine>::Scalar>,
     G2: Engine<Base = <G1 as Engine>::Scalar>,
 {
-    let root = current_dir().unwrap();
-    let witness_generator_output = root.join("circom_witness.wtns");
+    fold_inputs(
+        witness_generator_file,
+        r1cs,
+        private_inputs,
+        start_public_input,
+        pp,
+        false,
+    )
+}
 
-    let iteration_count = private_inputs.len();
+/// Variant of [`create_recursive_circuit`] that also records the public values between the steps
+/// in [`RecursiveOutput::trace`], e.g. to check them against an independent computation.
+#[cfg(feature = "prover")]
+pub fn create_recursive_circuit_with_trace<G1, G2>(
+    witness_generator_file: FileLocation,
+    r1cs: R1CS<F<G1>>,
+    private_inputs: Vec<HashMap<String, Value>>,
+    start_public_input: Vec<F<G1>>,
+    pp: &PublicParams<G1, G2, C1<G1>>,
+) -> Result<RecursiveOutput<G1, G2>, NovaScotiaError>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+{
+    fold_inputs(
+        witness_generator_file,
+        r1cs,
+        private_inputs,
+        start_public_input,
+        pp,
+        true,
+    )
+}
 
-    let start_public_input_hex = start_public_input
-        .iter()
-        .map(|&x| format!("{:?}", x).strip_prefix("0x").unwrap().to_string())
-        .collect::<Vec<String>>();
-    let mut current_public_input = start_public_input_hex.clone();
-
-    let witness_0 = compute_witness::<G1, G2>(
-        current_public_input.clone(),
-        private_inputs[0].clone(),
-        witness_generator_file.clone(),
-        &witness_generator_output,
-    );
+#[cfg(feature = "prover")]
+fn fold_inputs<G1, G2>(
+    witness_generator_file: FileLocation,
+    r1cs: R1CS<F<G1>>,
+    private_inputs: Vec<HashMap<String, Value>>,
+    start_public_input: Vec<F<G1>>,
+    pp: &PublicParams<G1, G2, C1<G1>>,
+    record_trace: bool,
+) -> Result<RecursiveOutput<G1, G2>, NovaScotiaError>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+{
+    let mut trace = record_trace.then(|| vec![start_public_input.clone()]);
+    let recursive_snark = fold_source(
+        None,
+        &start_public_input,
+        &Arc::new(r1cs),
+        pp,
+        generate_witnesses::<G1, G2, _>(&witness_generator_file, private_inputs.into_iter())?,
+        |_, snark| {
+            if let Some(trace) = trace.as_mut() {
+                trace.push(snark.outputs().to_vec());
+            }
+            Ok(())
+        },
+    )?;
+    recursive_output(recursive_snark, start_public_input, trace)
+}
 
-    let circuit_0 = CircomCircuit {
-        r1cs: r1cs.clone(),
-        witness: Some(witness_0),
-    };
-    let _circuit_secondary: TrivialCircuit<F<G2>> = TrivialCircuit::default();
-    let _z0_secondary = vec![<G2 as Engine>::Scalar::ZERO];
+// the output of folding from `z0`, failing if no step was folded
+#[cfg(feature = "prover")]
+fn recursive_output<G1, G2>(
+    recursive_snark: Option<RecursiveSNARK<G1, G2, C1<G1>>>,
+    z0: Vec<F<G1>>,
+    trace: Option<Vec<Vec<F<G1>>>>,
+) -> Result<RecursiveOutput<G1, G2>, NovaScotiaError>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+{
+    let recursive_snark = recursive_snark.ok_or_else(|| {
+        NovaScotiaError::InvalidInput("no private inputs were provided".to_string())
+    })?;
+    Ok(RecursiveOutput {
+        num_steps: recursive_snark.num_steps(),
+        z0,
+        zn: recursive_snark.outputs().to_vec(),
+        trace,
+        recursive_snark,
+    })
+}
 
-    let mut recursive_snark = RecursiveSNARK::<G1, G2, C1<G1>>::new(
-        &pp,
-        &circuit_0,
+/// Variant of [`create_recursive_circuit`] that pulls each step's private inputs from `source`
+/// just before computing its witness, and folds until the source runs out.
+#[cfg(feature = "prover")]
+pub fn create_recursive_circuit_from_source<G1, G2, S>(
+    witness_generator_file: FileLocation,
+    r1cs: R1CS<F<G1>>,
+    source: S,
+    start_public_input: Vec<F<G1>>,
+    pp: &PublicParams<G1, G2, C1<G1>>,
+) -> Result<RecursiveOutput<G1, G2>, NovaScotiaError>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+    S: StepInputSource<F<G1>>,
+{
+    let recursive_snark = fold_source(
+        None,
         &start_public_input,
+        &Arc::new(r1cs),
+        pp,
+        generate_witnesses::<G1, G2, _>(&witness_generator_file, source)?,
+        |_, _| Ok(()),
     )?;
+    recursive_output(recursive_snark, start_public_input, None)
+}
 
-    for i in 0..iteration_count {
-        let witness = compute_witness::<G1, G2>(
-            current_public_input.clone(),
-            private_inputs[i].clone(),
-            witness_generator_file.clone(),
-            &witness_generator_output,
-        );
+/// Variant of [`create_recursive_circuit`] for `num_steps` steps whose private inputs depend on
+/// the state folded so far, e.g. a Merkle path for the current root.
+///
+/// Before computing the witness of each step, `private_inputs` is called with the step's index and
+/// its `step_in`, the public outputs of the previous step.
+#[cfg(feature = "prover")]
+pub fn create_recursive_circuit_from_fn<G1, G2, P>(
+    witness_generator_file: FileLocation,
+    r1cs: R1CS<F<G1>>,
+    num_steps: usize,
+    mut private_inputs: P,
+    start_public_input: Vec<F<G1>>,
+    pp: &PublicParams<G1, G2, C1<G1>>,
+) -> Result<RecursiveOutput<G1, G2>, NovaScotiaError>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+    P: FnMut(usize, &[F<G1>]) -> HashMap<String, Value>,
+{
+    if num_steps == 0 {
+        return Err(NovaScotiaError::InvalidInput(
+            "a RecursiveSNARK needs at least one step".to_string(),
+        ));
+    }
+    create_recursive_circuit_from_source(
+        witness_generator_file,
+        r1cs,
+        inputs::from_fn(|step, z_i: &[F<G1>]| {
+            Ok((step < num_steps).then(|| private_inputs(step, z_i)))
+        }),
+        start_public_input,
+        pp,
+    )
+}
 
+// the witness of a step, or `None` once there are no more steps to fold
+#[cfg(feature = "prover")]
+type StepWitness<G> = Result<Option<Vec<F<G>>>, NovaScotiaError>;
+
+// a witness supplier for `fold_source` that pulls each step's private inputs from `source` and
+// runs the witness generator on them
+#[cfg(feature = "prover")]
+#[allow(clippy::type_complexity)]
+fn generate_witnesses<G1, G2, S>(
+    witness_generator_file: &FileLocation,
+    mut source: S,
+) -> Result<impl FnMut(usize, &[F<G1>]) -> StepWitness<G1>, NovaScotiaError>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+    S: StepInputSource<F<G1>>,
+{
+    let mut witness_generator = WitnessGenerator::load(witness_generator_file)?;
+    Ok(move |step, z_i: &[F<G1>]| {
+        source
+            .next_input(step, z_i)?
+            .map(|private_input| compute_witness(z_i, private_input, &mut witness_generator))
+            .transpose()
+    })
+}
+
+// fold one step per witness `next_witness` returns for the step's index and `step_in`, onto
+// `recursive_snark` or a new RecursiveSNARK started from `start_public_input`, calling
+// `after_step` with the index of each folded step
+#[cfg(feature = "prover")]
+fn fold_source<G1, G2>(
+    recursive_snark: Option<RecursiveSNARK<G1, G2, C1<G1>>>,
+    start_public_input: &[F<G1>],
+    r1cs: &Arc<R1CS<F<G1>>>,
+    pp: &PublicParams<G1, G2, C1<G1>>,
+    mut next_witness: impl FnMut(usize, &[F<G1>]) -> StepWitness<G1>,
+    mut after_step: impl FnMut(usize, &RecursiveSNARK<G1, G2, C1<G1>>) -> Result<(), NovaScotiaError>,
+) -> Result<Option<RecursiveSNARK<G1, G2, C1<G1>>>, NovaScotiaError>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+{
+    let mut recursive_snark = match recursive_snark {
+        Some(snark) => snark,
+        None => {
+            let witness = match next_witness(0, start_public_input)? {
+                Some(witness) => witness,
+                None => return Ok(None),
+            };
+            let circuit = CircomCircuit {
+                r1cs: r1cs.clone(),
+                witness: Some(witness),
+            };
+            let mut snark = RecursiveSNARK::new(pp, &circuit, start_public_input)
+                .map_err(|source| NovaScotiaError::Prover { step: 0, source })?;
+            snark
+                .prove_step(pp, &circuit)
+                .map_err(|source| NovaScotiaError::Prover { step: 0, source })?;
+            after_step(0, &snark)?;
+            snark
+        }
+    };
+    fold_steps(&mut recursive_snark, r1cs, pp, next_witness, after_step)?;
+    Ok(Some(recursive_snark))
+}
+
+// fold one step per witness `next_witness` returns onto `recursive_snark`, which has folded at
+// least one step, continuing from its outputs
+#[cfg(feature = "prover")]
+fn fold_steps<G1, G2>(
+    recursive_snark: &mut RecursiveSNARK<G1, G2, C1<G1>>,
+    r1cs: &Arc<R1CS<F<G1>>>,
+    pp: &PublicParams<G1, G2, C1<G1>>,
+    mut next_witness: impl FnMut(usize, &[F<G1>]) -> StepWitness<G1>,
+    mut after_step: impl FnMut(usize, &RecursiveSNARK<G1, G2, C1<G1>>) -> Result<(), NovaScotiaError>,
+) -> Result<(), NovaScotiaError>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+{
+    let mut z_i = recursive_snark.outputs().to_vec();
+    let mut step = recursive_snark.num_steps();
+    while let Some(witness) = next_witness(step, &z_i)? {
         let circuit = CircomCircuit {
             r1cs: r1cs.clone(),
             witness: Some(witness),
         };
+        z_i = circuit.get_public_outputs();
+        recursive_snark
+            .prove_step(pp, &circuit)
+            .map_err(|source| NovaScotiaError::Prover { step, source })?;
+        after_step(step, recursive_snark)?;
+        step += 1;
+    }
+    Ok(())
+}
 
-        let current_public_output = circuit.get_public_outputs();
-        current_public_input = current_public_output
-            .iter()
-            .map(|&x| format!("{:?}", x).strip_prefix("0x").unwrap().to_string())
-            .collect();
+/// Pipelined variant of [`create_recursive_circuit`].
+///
+/// Witnesses are computed by a pool of `lookahead` workers, each with its own witness generator,
+/// and at most `lookahead` steps ahead of `RecursiveSNARK::prove_step`. A step can only start once
+/// the `step_out` of the previous step is known, so WASM workers hand it on before they extract
+/// the rest of their witness, which then overlaps with the next step. The folded steps are
+/// identical to the serial path.
+#[cfg(feature = "prover")]
+pub fn create_recursive_circuit_pipelined<G1, G2>(
+    witness_generator_file: FileLocation,
+    r1cs: R1CS<F<G1>>,
+    private_inputs: Vec<HashMap<String, Value>>,
+    start_public_input: Vec<F<G1>>,
+    pp: &PublicParams<G1, G2, C1<G1>>,
+    lookahead: usize,
+) -> Result<RecursiveOutput<G1, G2>, NovaScotiaError>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+{
+    type Job<Fr> = (usize, Vec<Fr>, HashMap<String, Value>);
 
-        let res = recursive_snark.prove_step(
-            &pp,
-            &circuit,
-        );
-        assert!(res.is_ok());
+    let r1cs = Arc::new(r1cs);
+    let lookahead = lookahead.max(1);
+    let num_steps = private_inputs.len();
+
+    // steps whose `step_in` is known, taken by whichever worker is idle
+    let (job_sender, job_receiver) = mpsc::channel::<Job<F<G1>>>();
+    let job_receiver = Mutex::new(job_receiver);
+    // the `step_out` of the latest step, or `None` if its witness failed
+    let (outputs_sender, outputs_receiver) = mpsc::channel::<Option<Vec<F<G1>>>>();
+    // finished witnesses, in the order the workers complete them
+    let (witness_sender, witness_receiver) = mpsc::channel();
+    // a step may only be started while there are fewer than `lookahead` unfolded witnesses
+    let (token_sender, token_receiver) = mpsc::sync_channel(lookahead);
+    for _ in 0..lookahead {
+        token_sender
+            .send(())
+            .expect("the channel has room for every token");
     }
-    fs::remove_file(witness_generator_output)?;
 
-    Ok(recursive_snark)
+    thread::scope(|scope| {
+        // dropped when the prover returns, even with an error, which stops the dispatcher
+        let (token_sender, witness_receiver) = (token_sender, witness_receiver);
+        let start_public_input = &start_public_input;
+        scope.spawn(move || {
+            let mut z_i = start_public_input.clone();
+            for (step, private_input) in private_inputs.into_iter().enumerate() {
+                // the prover has given up, stop starting steps
+                if token_receiver.recv().is_err()
+                    || job_sender.send((step, z_i, private_input)).is_err()
+                {
+                    return;
+                }
+                match outputs_receiver.recv() {
+                    Ok(Some(step_out)) => z_i = step_out,
+                    _ => return,
+                }
+            }
+        });
+
+        for _ in 0..lookahead {
+            let (job_receiver, r1cs) = (&job_receiver, &r1cs);
+            let witness_generator_file = &witness_generator_file;
+            let outputs_sender = outputs_sender.clone();
+            let witness_sender = witness_sender.clone();
+            scope.spawn(move || {
+                let mut witness_generator = None;
+                loop {
+                    let job = job_receiver.lock().unwrap().recv();
+                    let Ok((step, z_i, private_input)) = job else {
+                        break;
+                    };
+                    let mut outputs_sender = Some(&outputs_sender);
+                    let witness = match &mut witness_generator {
+                        Some(witness_generator) => Ok(witness_generator),
+                        None => WitnessGenerator::load(witness_generator_file)
+                            .map(|loaded| witness_generator.insert(loaded)),
+                    }
+                    .and_then(|witness_generator| {
+                        compute_witness_ahead::<G1, G2>(
+                            &z_i,
+                            private_input,
+                            witness_generator,
+                            &r1cs.io.step_out,
+                            |step_out| {
+                                let _ = outputs_sender.take().unwrap().send(Some(step_out));
+                            },
+                        )
+                    });
+                    if let Some(outputs_sender) = outputs_sender {
+                        let _ = outputs_sender.send(None);
+                    }
+                    let _ = witness_sender.send((step, witness));
+                }
+            });
+        }
+        drop((outputs_sender, witness_sender));
+
+        // hand the witnesses to the prover in step order
+        let mut finished = BTreeMap::new();
+        let recursive_snark = fold_source(
+            None,
+            start_public_input,
+            &r1cs,
+            pp,
+            |step, _| {
+                if step == num_steps {
+                    return Ok(None);
+                }
+                let witness = loop {
+                    if let Some(witness) = finished.remove(&step) {
+                        break witness;
+                    }
+                    let (finished_step, witness) = witness_receiver.recv().map_err(|_| {
+                        NovaScotiaError::InvalidInput(format!(
+                            "the witness of step {} was never computed",
+                            step
+                        ))
+                    })?;
+                    finished.insert(finished_step, witness);
+                };
+                let _ = token_sender.send(());
+                witness.map(Some)
+            },
+            |_, _| Ok(()),
+        )?;
+        recursive_output(recursive_snark, start_public_input.clone(), None)
+    })
 }
 
+/// Fold the steps of `private_inputs` onto an existing `recursive_snark`, whose outputs the
+/// caller expects to be `last_zi`.
+///
+/// The snark is first verified against `start_public_input` and `pp`, and its outputs compared
+/// with `last_zi`, so a snark that was started from a different `z_0`, does not verify at all, or
+/// has moved on since `last_zi` was recorded is rejected before anything is folded onto it.
+/// Returns the new step count and outputs.
+#[cfg(feature = "prover")]
 pub fn continue_recursive_circuit<G1, G2>(
     recursive_snark: &mut RecursiveSNARK<G1, G2, C1<G1>>,
     last_zi: Vec<F<G1>>,
     witness_generator_file: FileLocation,
     r1cs: R1CS<F<G1>>,
     private_inputs: Vec<HashMap<String, Value>>,
-    _start_public_input: Vec<F<G1>>,
+    start_public_input: Vec<F<G1>>,
+    pp: &PublicParams<G1, G2, C1<G1>>,
+) -> Result<(usize, Vec<F<G1>>), NovaScotiaError>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+{
+    let start_step = recursive_snark.num_steps();
+    let z_i = recursive_snark
+        .verify(pp, start_step, &start_public_input)
+        .map_err(|err| {
+            NovaScotiaError::InvalidInput(format!(
+                "RecursiveSNARK does not verify from start_public_input after {} steps: {}",
+                start_step, err
+            ))
+        })?;
+    if z_i != last_zi {
+        return Err(NovaScotiaError::InvalidInput(format!(
+            "last_zi [{}] is not the output of the RecursiveSNARK after {} steps, [{}]",
+            last_zi
+                .iter()
+                .map(convert::to_decimal)
+                .collect::<Vec<_>>()
+                .join(", "),
+            start_step,
+            z_i.iter()
+                .map(convert::to_decimal)
+                .collect::<Vec<_>>()
+                .join(", ")
+        )));
+    }
+
+    fold_steps(
+        recursive_snark,
+        &Arc::new(r1cs),
+        pp,
+        generate_witnesses::<G1, G2, _>(&witness_generator_file, private_inputs.into_iter())?,
+        |_, _| Ok(()),
+    )?;
+    Ok((
+        recursive_snark.num_steps(),
+        recursive_snark.outputs().to_vec(),
+    ))
+}
+
+/// Variant of [`create_recursive_circuit`] that saves a [`Checkpoint`] to `checkpoint_file`
+/// every `checkpoint_interval` steps and after the last step.
+///
+/// A `checkpoint_interval` of 0 only saves the final checkpoint. If the process dies, pass the
+/// last checkpoint and the same private inputs to [`resume_recursive_circuit`].
+#[cfg(feature = "prover")]
+pub fn create_recursive_circuit_with_checkpoints<G1, G2>(
+    witness_generator_file: FileLocation,
+    r1cs: R1CS<F<G1>>,
+    private_inputs: Vec<HashMap<String, Value>>,
+    start_public_input: Vec<F<G1>>,
     pp: &PublicParams<G1, G2, C1<G1>>,
-) -> Result<(), Box<dyn std::error::Error>>
+    checkpoint_file: &Path,
+    checkpoint_interval: usize,
+) -> Result<RecursiveOutput<G1, G2>, NovaScotiaError>
 where
     G1: Engine<Base = <G2 as Engine>::Scalar>,
     G2: Engine<Base = <G1 as Engine>::Scalar>,
 {
-    let root = current_dir().unwrap();
-    let witness_generator_output = root.join("circom_witness.wtns");
+    fold_with_checkpoints(
+        None,
+        start_public_input,
+        witness_generator_file,
+        r1cs,
+        private_inputs.into_iter(),
+        pp,
+        checkpoint_file,
+        checkpoint_interval,
+    )
+}
 
-    let iteration_count = private_inputs.len();
+/// Variant of [`create_recursive_circuit_with_checkpoints`] that pulls each step's private inputs
+/// from `source`, see [`create_recursive_circuit_from_source`].
+#[cfg(feature = "prover")]
+pub fn create_recursive_circuit_with_checkpoints_from_source<G1, G2, S>(
+    witness_generator_file: FileLocation,
+    r1cs: R1CS<F<G1>>,
+    source: S,
+    start_public_input: Vec<F<G1>>,
+    pp: &PublicParams<G1, G2, C1<G1>>,
+    checkpoint_file: &Path,
+    checkpoint_interval: usize,
+) -> Result<RecursiveOutput<G1, G2>, NovaScotiaError>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+    S: StepInputSource<F<G1>>,
+{
+    fold_with_checkpoints(
+        None,
+        start_public_input,
+        witness_generator_file,
+        r1cs,
+        source,
+        pp,
+        checkpoint_file,
+        checkpoint_interval,
+    )
+}
 
-    let mut current_public_input = last_zi
+/// Continue a run of [`create_recursive_circuit_with_checkpoints`] from its last checkpoint.
+///
+/// `private_inputs` are the inputs of the whole run: the steps the checkpoint already folded are
+/// skipped. Fails with [`NovaScotiaError::DigestMismatch`] if the checkpoint was taken with
+/// different public parameters.
+#[cfg(feature = "prover")]
+pub fn resume_recursive_circuit<G1, G2>(
+    checkpoint: Checkpoint<G1, G2>,
+    witness_generator_file: FileLocation,
+    r1cs: R1CS<F<G1>>,
+    private_inputs: Vec<HashMap<String, Value>>,
+    pp: &PublicParams<G1, G2, C1<G1>>,
+    checkpoint_file: &Path,
+    checkpoint_interval: usize,
+) -> Result<RecursiveOutput<G1, G2>, NovaScotiaError>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+{
+    checkpoint.check_params(pp)?;
+    if checkpoint.num_steps > private_inputs.len() {
+        return Err(NovaScotiaError::InvalidInput(format!(
+            "checkpoint has folded {} steps, but only {} private inputs were provided",
+            checkpoint.num_steps,
+            private_inputs.len()
+        )));
+    }
+    let remaining_inputs = private_inputs.into_iter().skip(checkpoint.num_steps);
+    fold_with_checkpoints(
+        Some(checkpoint.recursive_snark),
+        checkpoint.z0,
+        witness_generator_file,
+        r1cs,
+        remaining_inputs,
+        pp,
+        checkpoint_file,
+        checkpoint_interval,
+    )
+}
+
+/// Variant of [`resume_recursive_circuit`] that pulls the private inputs of the remaining steps
+/// from `source`.
+///
+/// Unlike [`resume_recursive_circuit`], `source` must not supply the steps the checkpoint already
+/// folded: it is first asked for step `checkpoint.num_steps`, with the checkpoint's outputs as
+/// `step_in`.
+#[cfg(feature = "prover")]
+pub fn resume_recursive_circuit_from_source<G1, G2, S>(
+    checkpoint: Checkpoint<G1, G2>,
+    witness_generator_file: FileLocation,
+    r1cs: R1CS<F<G1>>,
+    source: S,
+    pp: &PublicParams<G1, G2, C1<G1>>,
+    checkpoint_file: &Path,
+    checkpoint_interval: usize,
+) -> Result<RecursiveOutput<G1, G2>, NovaScotiaError>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+    S: StepInputSource<F<G1>>,
+{
+    checkpoint.check_params(pp)?;
+    fold_with_checkpoints(
+        Some(checkpoint.recursive_snark),
+        checkpoint.z0,
+        witness_generator_file,
+        r1cs,
+        source,
+        pp,
+        checkpoint_file,
+        checkpoint_interval,
+    )
+}
+
+#[cfg(feature = "prover")]
+#[allow(clippy::too_many_arguments)]
+fn fold_with_checkpoints<G1, G2, S>(
+    recursive_snark: Option<RecursiveSNARK<G1, G2, C1<G1>>>,
+    start_public_input: Vec<F<G1>>,
+    witness_generator_file: FileLocation,
+    r1cs: R1CS<F<G1>>,
+    source: S,
+    pp: &PublicParams<G1, G2, C1<G1>>,
+    checkpoint_file: &Path,
+    checkpoint_interval: usize,
+) -> Result<RecursiveOutput<G1, G2>, NovaScotiaError>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+    S: StepInputSource<F<G1>>,
+{
+    let recursive_snark = fold_source(
+        recursive_snark,
+        &start_public_input,
+        &Arc::new(r1cs),
+        pp,
+        generate_witnesses::<G1, G2, _>(&witness_generator_file, source)?,
+        |step, snark| {
+            if checkpoint_interval > 0 && (step + 1).is_multiple_of(checkpoint_interval) {
+                save_checkpoint(checkpoint_file, snark, &start_public_input, pp)?;
+            }
+            Ok(())
+        },
+    )?;
+    let output = recursive_output(recursive_snark, start_public_input, None)?;
+    save_checkpoint(checkpoint_file, &output.recursive_snark, &output.z0, pp)?;
+    Ok(output)
+}
+
+/// Create the SuperNova public parameters of a non-uniform program, with one R1CS shape per
+/// circuit.
+#[cfg(feature = "supernova")]
+pub fn create_nonuniform_public_params<E1, E2>(
+    program: &NonUniformProgram<E1::Scalar>,
+) -> NonUniformPublicParams<E1, E2>
+where
+    E1: SuperNovaEngine<Base = <E2 as SuperNovaEngine>::Scalar>,
+    E2: SuperNovaEngine<Base = <E1 as SuperNovaEngine>::Scalar>,
+{
+    let program = ProgramRun {
+        program,
+        start_pc: 0,
+    };
+    NonUniformPublicParams::setup(
+        &program,
+        &*arecibo::traits::snark::default_ck_hint(),
+        &*arecibo::traits::snark::default_ck_hint(),
+    )
+}
+
+/// A folded SuperNova proof of a non-uniform program together with its public values, see
+/// [`RecursiveOutput`].
+#[cfg(feature = "supernova")]
+pub struct NonUniformOutput<E1, E2>
+where
+    E1: SuperNovaEngine<Base = <E2 as SuperNovaEngine>::Scalar>,
+    E2: SuperNovaEngine<Base = <E1 as SuperNovaEngine>::Scalar>,
+{
+    pub recursive_snark: SuperNovaSNARK<E1, E2>,
+    pub num_steps: usize,
+    /// `step_in` of the first step
+    pub z0: Vec<E1::Scalar>,
+    /// `step_out` of the last step, the outputs [`verify_nonuniform_recursive_circuit`] returns
+    pub zn: Vec<E1::Scalar>,
+    /// `next_pc` of the last step, i.e. the circuit a further step would run
+    pub pc: E1::Scalar,
+}
+
+/// Fold one step per private input with SuperNova, starting with the circuit at `start_pc`.
+///
+/// Each step runs the witness generator of the circuit selected by the current program counter,
+/// whose `next_pc` output selects the circuit of the following step, and only that circuit is
+/// folded.
+#[cfg(feature = "supernova")]
+pub fn create_nonuniform_recursive_circuit<E1, E2>(
+    program: &NonUniformProgram<E1::Scalar>,
+    private_inputs: Vec<HashMap<String, Value>>,
+    start_pc: usize,
+    start_public_input: Vec<E1::Scalar>,
+    pp: &NonUniformPublicParams<E1, E2>,
+) -> Result<NonUniformOutput<E1, E2>, NovaScotiaError>
+where
+    E1: SuperNovaEngine<Base = <E2 as SuperNovaEngine>::Scalar>,
+    E2: SuperNovaEngine<Base = <E1 as SuperNovaEngine>::Scalar>,
+{
+    let run = ProgramRun { program, start_pc };
+    let circuit_secondary = SecondaryCircuit::<E2>::default();
+    let z0_secondary = vec![E2::Scalar::ZERO];
+    let mut pc = E1::Scalar::from(start_pc as u64);
+    let mut current_public_input = start_public_input.clone();
+    let mut witness_generators = program
+        .circuits()
         .iter()
-        .map(|&x| format!("{:?}", x).strip_prefix("0x").unwrap().to_string())
-        .collect::<Vec<String>>();
+        .map(|circuit| WitnessGenerator::load(&circuit.witness_generator_file))
+        .collect::<Result<Vec<_>, _>>()?;
 
-    let _circuit_secondary: TrivialCircuit<F<G2>> = TrivialCircuit::default();
-    let _z0_secondary = vec![<G2 as Engine>::Scalar::ZERO];
+    let mut recursive_snark: Option<SuperNovaSNARK<E1, E2>> = None;
+    let mut num_steps = 0;
+    for (step, private_input) in private_inputs.into_iter().enumerate() {
+        let index = program.resolve_pc(pc).ok_or_else(|| {
+            NovaScotiaError::InvalidInput(format!(
+                "step {} selected program counter {:?}, but only {} circuits are registered",
+                step,
+                pc,
+                program.circuits().len()
+            ))
+        })?;
+        let witness = compute_witness(
+            &current_public_input,
+            private_input,
+            &mut witness_generators[index],
+        )?;
 
-    for i in 0..iteration_count {
-        let witness = compute_witness::<G1, G2>(
-            current_public_input.clone(),
-            private_inputs[i].clone(),
+        let circuit = program.step(index, Some(witness));
+        pc = circuit.next_pc();
+        let current_public_output = circuit.get_public_outputs();
+
+        let snark = match recursive_snark.as_mut() {
+            Some(snark) => snark,
+            None => recursive_snark.insert(
+                SuperNovaSNARK::new(
+                    pp,
+                    &run,
+                    &circuit,
+                    &circuit_secondary,
+                    &start_public_input,
+                    &z0_secondary,
+                )
+                .map_err(|source| NovaScotiaError::SuperNovaProver { step, source })?,
+            ),
+        };
+        snark
+            .prove_step(pp, &circuit, &circuit_secondary)
+            .map_err(|source| NovaScotiaError::SuperNovaProver { step, source })?;
+        current_public_input = current_public_output;
+        num_steps += 1;
+    }
+
+    let recursive_snark = recursive_snark.ok_or_else(|| {
+        NovaScotiaError::InvalidInput("no private inputs were provided".to_string())
+    })?;
+    Ok(NonUniformOutput {
+        recursive_snark,
+        num_steps,
+        z0: start_public_input,
+        zn: current_public_input,
+        pc,
+    })
+}
+
+/// Verify a proof of [`create_nonuniform_recursive_circuit`] whose first step had
+/// `start_public_input` as its `step_in`, returning the `step_out` of its last step.
+#[cfg(feature = "supernova")]
+pub fn verify_nonuniform_recursive_circuit<E1, E2>(
+    recursive_snark: &SuperNovaSNARK<E1, E2>,
+    pp: &NonUniformPublicParams<E1, E2>,
+    start_public_input: &[E1::Scalar],
+) -> Result<Vec<E1::Scalar>, NovaScotiaError>
+where
+    E1: SuperNovaEngine<Base = <E2 as SuperNovaEngine>::Scalar>,
+    E2: SuperNovaEngine<Base = <E1 as SuperNovaEngine>::Scalar>,
+{
+    let (zn, _) = recursive_snark.verify(pp, start_public_input, &[E2::Scalar::ZERO])?;
+    Ok(zn)
+}
+
+#[cfg(all(test, feature = "prover"))]
+mod tests {
+    use super::*;
+    use crate::circom::reader::load_r1cs;
+    use serde_json::json;
+    use std::path::Path;
+
+    type G1 = nova_snark::provider::PallasEngine;
+    type G2 = nova_snark::provider::VestaEngine;
+
+    fn toy_r1cs() -> R1CS<F<G1>> {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        load_r1cs::<G1, G2>(&FileLocation::PathBuf(
+            root.join("examples/toy/pasta/toy.r1cs"),
+        ))
+        .unwrap()
+    }
+
+    fn toy_witness_generator() -> FileLocation {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        FileLocation::PathBuf(root.join("examples/toy/pasta/toy_js/toy.wasm"))
+    }
+
+    fn toy_private_inputs(iteration_count: usize) -> Vec<HashMap<String, Value>> {
+        (0..iteration_count)
+            .map(|i| HashMap::from([("adder".to_string(), json!(i))]))
+            .collect()
+    }
+
+    #[test]
+    fn test_recursive_circuit_with_wasm_witness() {
+        let r1cs = toy_r1cs();
+        let witness_generator_file = toy_witness_generator();
+
+        let iteration_count = 3;
+        let start_public_input = vec![F::<G1>::from(10), F::<G1>::from(10)];
+
+        let pp = create_public_params::<G1, G2>(r1cs.clone()).unwrap();
+        let output = create_recursive_circuit(
             witness_generator_file.clone(),
-            &witness_generator_output,
+            r1cs.clone(),
+            toy_private_inputs(iteration_count),
+            start_public_input.clone(),
+            &pp,
+        )
+        .unwrap();
+
+        let z_n = output
+            .recursive_snark
+            .verify(&pp, iteration_count, &start_public_input)
+            .unwrap();
+        // (10, 10) -> (10, 20) -> (11, 30) -> (13, 41)
+        assert_eq!(z_n, vec![F::<G1>::from(13), F::<G1>::from(41)]);
+        assert_eq!(output.num_steps, iteration_count);
+        assert_eq!(output.z0, start_public_input);
+        assert_eq!(output.zn, z_n);
+        assert!(output.trace.is_none());
+
+        let output = create_recursive_circuit_with_trace(
+            witness_generator_file,
+            r1cs,
+            toy_private_inputs(iteration_count),
+            start_public_input.clone(),
+            &pp,
+        )
+        .unwrap();
+        assert_eq!(
+            output.trace.unwrap(),
+            [(10, 10), (10, 20), (11, 30), (13, 41)]
+                .map(|(a, b)| vec![F::<G1>::from(a), F::<G1>::from(b)])
         );
+    }
 
-        let circuit = CircomCircuit {
-            r1cs: r1cs.clone(),
-            witness: Some(witness),
-        };
+    #[test]
+    fn test_pipelined_recursive_circuit() {
+        let r1cs = toy_r1cs();
+        let witness_generator_file = toy_witness_generator();
 
-        let current_public_output = circuit.get_public_outputs();
-        current_public_input = current_public_output
-            .iter()
-            .map(|&x| format!("{:?}", x).strip_prefix("0x").unwrap().to_string())
-            .collect();
+        let iteration_count = 4;
+        let start_public_input = vec![F::<G1>::from(10), F::<G1>::from(10)];
 
-        let res = recursive_snark.prove_step(
-            pp,
-            &circuit,
+        let pp = create_public_params::<G1, G2>(r1cs.clone()).unwrap();
+        let serial = create_recursive_circuit(
+            witness_generator_file.clone(),
+            r1cs.clone(),
+            toy_private_inputs(iteration_count),
+            start_public_input.clone(),
+            &pp,
+        )
+        .unwrap();
+
+        for lookahead in [0, 1, 3] {
+            let output = create_recursive_circuit_pipelined(
+                witness_generator_file.clone(),
+                r1cs.clone(),
+                toy_private_inputs(iteration_count),
+                start_public_input.clone(),
+                &pp,
+                lookahead,
+            )
+            .unwrap();
+
+            assert_eq!(output.num_steps, iteration_count);
+            assert_eq!(output.z0, start_public_input);
+            assert_eq!(output.zn, serial.zn);
+            let z_n = output
+                .recursive_snark
+                .verify(&pp, iteration_count, &start_public_input)
+                .unwrap();
+            assert_eq!(z_n, serial.zn);
+        }
+
+        // a failing step is reported instead of leaving the workers waiting
+        let mut private_inputs = toy_private_inputs(iteration_count);
+        private_inputs[1].insert("unknown".to_string(), json!(1));
+        let res = create_recursive_circuit_pipelined(
+            witness_generator_file,
+            r1cs,
+            private_inputs,
+            start_public_input,
+            &pp,
+            2,
         );
-        assert!(res.is_ok());
+        assert!(matches!(res, Err(NovaScotiaError::Wasm(_))));
     }
 
-    fs::remove_file(witness_generator_output)?;
+    #[test]
+    fn test_recursive_circuit_from_source() {
+        let r1cs = toy_r1cs();
+        let witness_generator_file = toy_witness_generator();
+        let start_public_input = vec![F::<G1>::from(10), F::<G1>::from(10)];
+        let pp = create_public_params::<G1, G2>(r1cs.clone()).unwrap();
 
-    Ok(())
+        // fold until the second output reaches 30, however many steps that takes
+        let mut seen = vec![];
+        let source = inputs::from_fn(|step, z_i: &[F<G1>]| {
+            seen.push(z_i.to_vec());
+            Ok((z_i[1] != F::<G1>::from(30))
+                .then(|| HashMap::from([("adder".to_string(), json!(step))])))
+        });
+        let output = create_recursive_circuit_from_source(
+            witness_generator_file.clone(),
+            r1cs.clone(),
+            source,
+            start_public_input.clone(),
+            &pp,
+        )
+        .unwrap();
+        assert_eq!(output.num_steps, 2);
+        assert_eq!(output.zn, vec![F::<G1>::from(11), F::<G1>::from(30)]);
+        assert_eq!(
+            seen,
+            [(10, 10), (10, 20), (11, 30)].map(|(a, b)| vec![F::<G1>::from(a), F::<G1>::from(b)])
+        );
+
+        // inputs that arrive over a channel are folded until the sender hangs up
+        let (sender, receiver) = mpsc::channel();
+        let output = thread::scope(|scope| {
+            scope.spawn(move || {
+                for input in toy_private_inputs(3) {
+                    sender.send(input).unwrap();
+                }
+            });
+            create_recursive_circuit_from_source(
+                witness_generator_file,
+                r1cs,
+                receiver.into_iter(),
+                start_public_input.clone(),
+                &pp,
+            )
+            .unwrap()
+        });
+        let z_n = output
+            .recursive_snark
+            .verify(&pp, 3, &start_public_input)
+            .unwrap();
+        assert_eq!(z_n, vec![F::<G1>::from(13), F::<G1>::from(41)]);
+        assert_eq!(output.zn, z_n);
+    }
+
+    #[test]
+    fn test_recursive_circuit_from_fn() {
+        let r1cs = toy_r1cs();
+        let witness_generator_file = toy_witness_generator();
+        let start_public_input = vec![F::<G1>::from(10), F::<G1>::from(10)];
+        let pp = create_public_params::<G1, G2>(r1cs.clone()).unwrap();
+
+        // add 5 whenever the second output is 20, and 1 otherwise
+        let output = create_recursive_circuit_from_fn(
+            witness_generator_file,
+            r1cs,
+            3,
+            |_, z_i: &[F<G1>]| {
+                let adder = if z_i[1] == F::<G1>::from(20) { 5 } else { 1 };
+                HashMap::from([("adder".to_string(), json!(adder))])
+            },
+            start_public_input.clone(),
+            &pp,
+        )
+        .unwrap();
+        let z_n = output
+            .recursive_snark
+            .verify(&pp, 3, &start_public_input)
+            .unwrap();
+        // (10, 10) -> (11, 20) -> (16, 31) -> (17, 47)
+        assert_eq!(z_n, vec![F::<G1>::from(17), F::<G1>::from(47)]);
+        assert_eq!((output.num_steps, output.zn), (3, z_n));
+    }
+
+    #[test]
+    fn test_continue_recursive_circuit() {
+        let r1cs = toy_r1cs();
+        let witness_generator_file = toy_witness_generator();
+        let start_public_input = vec![F::<G1>::from(10), F::<G1>::from(10)];
+        let pp = create_public_params::<G1, G2>(r1cs.clone()).unwrap();
+
+        let mut recursive_snark = create_recursive_circuit(
+            witness_generator_file.clone(),
+            r1cs.clone(),
+            toy_private_inputs(2),
+            start_public_input.clone(),
+            &pp,
+        )
+        .unwrap()
+        .recursive_snark;
+        let last_zi = vec![F::<G1>::from(11), F::<G1>::from(30)];
+        assert_eq!(recursive_snark.outputs(), last_zi);
+
+        // a snark started from a different z_0 is not extended
+        let err = continue_recursive_circuit(
+            &mut recursive_snark,
+            last_zi.clone(),
+            witness_generator_file.clone(),
+            r1cs.clone(),
+            toy_private_inputs(1),
+            vec![F::<G1>::from(10), F::<G1>::from(11)],
+            &pp,
+        )
+        .err()
+        .unwrap();
+        assert!(matches!(err, NovaScotiaError::InvalidInput(_)), "{}", err);
+
+        // and neither is one whose outputs are not the `last_zi` the caller expects
+        let err = continue_recursive_circuit(
+            &mut recursive_snark,
+            vec![F::<G1>::from(10), F::<G1>::from(20)],
+            witness_generator_file.clone(),
+            r1cs.clone(),
+            toy_private_inputs(1),
+            start_public_input.clone(),
+            &pp,
+        )
+        .err()
+        .unwrap();
+        assert!(
+            err.to_string()
+                .contains("last_zi [10, 20] is not the output of the RecursiveSNARK after 2 steps"),
+            "{}",
+            err
+        );
+
+        let (num_steps, z_last) = continue_recursive_circuit(
+            &mut recursive_snark,
+            last_zi,
+            witness_generator_file,
+            r1cs,
+            toy_private_inputs(2),
+            start_public_input.clone(),
+            &pp,
+        )
+        .unwrap();
+        // (10, 10) -> (10, 20) -> (11, 30) -> (11, 41) -> (12, 52)
+        assert_eq!(num_steps, 4);
+        assert_eq!(z_last, vec![F::<G1>::from(12), F::<G1>::from(52)]);
+        assert_eq!(
+            recursive_snark.verify(&pp, 4, &start_public_input).unwrap(),
+            z_last
+        );
+    }
+
+    #[test]
+    fn test_witness_error_is_returned() {
+        let r1cs = toy_r1cs();
+        let witness_generator_file = toy_witness_generator();
+        let pp = create_public_params::<G1, G2>(r1cs.clone()).unwrap();
+
+        // the toy circuit has two `step_in` signals
+        let res = create_recursive_circuit(
+            witness_generator_file,
+            r1cs,
+            toy_private_inputs(2),
+            vec![F::<G1>::from(10)],
+            &pp,
+        );
+        assert!(matches!(res, Err(NovaScotiaError::Wasm(_))));
+    }
+
+    #[test]
+    fn test_resume_from_checkpoint() {
+        let r1cs = toy_r1cs();
+        let witness_generator_file = toy_witness_generator();
+        let dir = tempfile::tempdir().unwrap();
+        let checkpoint_file = dir.path().join("toy.checkpoint");
+
+        let start_public_input = vec![F::<G1>::from(10), F::<G1>::from(10)];
+        let pp = create_public_params::<G1, G2>(r1cs.clone()).unwrap();
+
+        // a run that was interrupted after its first two steps
+        create_recursive_circuit_with_checkpoints(
+            witness_generator_file.clone(),
+            r1cs.clone(),
+            toy_private_inputs(2),
+            start_public_input.clone(),
+            &pp,
+            &checkpoint_file,
+            1,
+        )
+        .unwrap();
+
+        let checkpoint = Checkpoint::<G1, G2>::load(&checkpoint_file).unwrap();
+        assert_eq!(checkpoint.num_steps, 2);
+        assert_eq!(checkpoint.z0, start_public_input);
+        assert_eq!(checkpoint.zi, vec![F::<G1>::from(11), F::<G1>::from(30)]);
+
+        // parameters for a different circuit are rejected
+        let mut other_r1cs = r1cs.clone();
+        other_r1cs
+            .constraints
+            .push(other_r1cs.constraints[0].clone());
+        let other_pp = create_public_params::<G1, G2>(other_r1cs).unwrap();
+        assert!(matches!(
+            checkpoint.check_params(&other_pp),
+            Err(NovaScotiaError::DigestMismatch(_))
+        ));
+
+        let output = resume_recursive_circuit(
+            checkpoint,
+            witness_generator_file,
+            r1cs,
+            toy_private_inputs(3),
+            &pp,
+            &checkpoint_file,
+            1,
+        )
+        .unwrap();
+        assert_eq!(output.z0, start_public_input);
+        let z_n = output
+            .recursive_snark
+            .verify(&pp, 3, &start_public_input)
+            .unwrap();
+        assert_eq!(z_n, vec![F::<G1>::from(13), F::<G1>::from(41)]);
+        assert_eq!((output.num_steps, output.zn), (3, z_n));
+        assert_eq!(
+            Checkpoint::<G1, G2>::load(&checkpoint_file)
+                .unwrap()
+                .num_steps,
+            3
+        );
+    }
 }
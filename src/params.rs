@@ -0,0 +1,283 @@
+// Persisted public parameters.
+//
+// A params file is the magic `nspp`, a little-endian u32 format version, a compression byte
+// (0 = none, 1 = gzip), the 32-byte digest of the R1CS the parameters were created for, the
+// digest of the parameters themselves as a field element, and the bincode encoding of the
+// `PublicParams`, gzipped if requested. The R1CS digest is checked before the parameters are
+// decoded, so a params file for a different circuit is rejected cheaply, and the parameters
+// digest is checked after, so a file whose parameters were replaced is rejected too.
+use std::fs::File;
+use std::io::{BufReader, Read, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use ff::{Field, PrimeField};
+use flate2::{read::GzDecoder, write::GzEncoder};
+use nova_snark::{
+    errors::NovaError,
+    nova::{PublicParams, RecursiveSNARK},
+    traits::Engine,
+};
+
+use crate::circom::circuit::{CircomCircuit, R1CS};
+use crate::convert::to_hex;
+use crate::error::NovaScotiaError;
+use crate::{write_atomically, C1, F};
+
+const MAGIC: &[u8; 4] = b"nspp";
+const VERSION: u32 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+}
+
+impl Compression {
+    fn to_byte(self) -> u8 {
+        match self {
+            Compression::None => 0,
+            Compression::Gzip => 1,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Result<Self, NovaScotiaError> {
+        match byte {
+            0 => Ok(Compression::None),
+            1 => Ok(Compression::Gzip),
+            _ => Err(NovaScotiaError::InvalidInput(format!(
+                "unknown public parameters compression {}",
+                byte
+            ))),
+        }
+    }
+}
+
+/// write public parameters created for `r1cs` to `path`, replacing any previous file atomically so
+/// that a crash while saving never leaves a truncated params file behind
+///
+/// Fails if `pp` was created for a circuit with a different arity than `r1cs`.
+pub fn save_public_params<G1, G2>(
+    pp: &PublicParams<G1, G2, C1<G1>>,
+    r1cs: &R1CS<F<G1>>,
+    path: &Path,
+    compression: Compression,
+) -> Result<(), NovaScotiaError>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+{
+    write_atomically(path, |writer| {
+        write_public_params(writer, pp, r1cs, compression)
+    })
+}
+
+/// load public parameters from `path`, failing if they were saved for a circuit other than `r1cs`
+pub fn load_public_params<G1, G2>(
+    path: &Path,
+    r1cs: &R1CS<F<G1>>,
+) -> Result<PublicParams<G1, G2, C1<G1>>, NovaScotiaError>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+{
+    read_public_params(BufReader::new(File::open(path)?), r1cs)
+}
+
+/// stream public parameters created for `r1cs` to a writer, failing if `pp` was created for a
+/// circuit with a different arity
+pub fn write_public_params<G1, G2, W: Write>(
+    mut writer: W,
+    pp: &PublicParams<G1, G2, C1<G1>>,
+    r1cs: &R1CS<F<G1>>,
+    compression: Compression,
+) -> Result<(), NovaScotiaError>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+{
+    check_arity(pp, r1cs)?;
+    writer.write_all(MAGIC)?;
+    writer.write_u32::<LittleEndian>(VERSION)?;
+    writer.write_u8(compression.to_byte())?;
+    writer.write_all(&r1cs.digest())?;
+    writer.write_all(pp.digest().to_repr().as_ref())?;
+    match compression {
+        Compression::None => bincode::serialize_into(writer, pp)?,
+        Compression::Gzip => {
+            let mut encoder = GzEncoder::new(writer, flate2::Compression::default());
+            bincode::serialize_into(&mut encoder, pp)?;
+            encoder.finish()?;
+        }
+    }
+    Ok(())
+}
+
+/// The header of a params file, which describes the parameters without decoding them.
+#[derive(Debug, Clone)]
+pub struct ParamsHeader<Fr: PrimeField> {
+    pub version: u32,
+    pub compression: Compression,
+    /// digest of the R1CS the parameters were created for
+    pub r1cs_digest: [u8; 32],
+    pub pp_digest: Fr,
+}
+
+/// read and validate the header of a params file, leaving `reader` at the start of the
+/// parameters
+pub fn read_params_header<G1, G2, R: Read>(
+    reader: &mut R,
+) -> Result<ParamsHeader<F<G1>>, NovaScotiaError>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+{
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(NovaScotiaError::InvalidInput(
+            "not a Nova Scotia public parameters file".to_string(),
+        ));
+    }
+    let version = reader.read_u32::<LittleEndian>()?;
+    if version != VERSION {
+        return Err(NovaScotiaError::InvalidInput(format!(
+            "unsupported public parameters version {}",
+            version
+        )));
+    }
+    let compression = Compression::from_byte(reader.read_u8()?)?;
+    let mut r1cs_digest = [0u8; 32];
+    reader.read_exact(&mut r1cs_digest)?;
+    let mut repr = <F<G1> as PrimeField>::Repr::default();
+    reader.read_exact(repr.as_mut())?;
+    let pp_digest = Option::from(F::<G1>::from_repr(repr)).ok_or_else(|| {
+        NovaScotiaError::NonCanonicalFieldElement(
+            "public parameters digest is outside the field".to_string(),
+        )
+    })?;
+    Ok(ParamsHeader {
+        version,
+        compression,
+        r1cs_digest,
+        pp_digest,
+    })
+}
+
+/// stream public parameters from a reader, failing if they were written for a circuit other
+/// than `r1cs`
+pub fn read_public_params<G1, G2, R: Read>(
+    mut reader: R,
+    r1cs: &R1CS<F<G1>>,
+) -> Result<PublicParams<G1, G2, C1<G1>>, NovaScotiaError>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+{
+    let header = read_params_header::<G1, G2, _>(&mut reader)?;
+    let expected = r1cs.digest();
+    if header.r1cs_digest != expected {
+        return Err(NovaScotiaError::DigestMismatch(format!(
+            "public parameters were created for R1CS {}, but loading for {}",
+            to_hex(&header.r1cs_digest),
+            to_hex(&expected)
+        )));
+    }
+    let pp: PublicParams<G1, G2, C1<G1>> = match header.compression {
+        Compression::None => bincode::deserialize_from(reader)?,
+        Compression::Gzip => bincode::deserialize_from(GzDecoder::new(reader))?,
+    };
+    if pp.digest() != header.pp_digest {
+        return Err(NovaScotiaError::DigestMismatch(
+            "public parameters do not match the digest in their header".to_string(),
+        ));
+    }
+    Ok(pp)
+}
+
+// `PublicParams` keeps the shape of its circuit private, so the only property of `r1cs` it can be
+// checked against is the arity: start a proof with it, which Nova rejects if the number of step
+// inputs differs from the circuit the parameters were set up for
+fn check_arity<G1, G2>(
+    pp: &PublicParams<G1, G2, C1<G1>>,
+    r1cs: &R1CS<F<G1>>,
+) -> Result<(), NovaScotiaError>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+{
+    let circuit = CircomCircuit {
+        r1cs: Arc::new(r1cs.clone()),
+        witness: None,
+    };
+    let z0 = vec![F::<G1>::ZERO; r1cs.io.arity()];
+    match RecursiveSNARK::new(pp, &circuit, &z0) {
+        Err(NovaError::InvalidInitialInputLength) => Err(NovaScotiaError::DigestMismatch(format!(
+            "public parameters were not created for an R1CS with {} step inputs",
+            r1cs.io.arity()
+        ))),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circom::reader::load_r1cs;
+    use crate::{create_public_params, FileLocation};
+
+    type G1 = nova_snark::provider::PallasEngine;
+    type G2 = nova_snark::provider::VestaEngine;
+
+    #[test]
+    fn test_public_params_round_trip() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let r1cs = load_r1cs::<G1, G2>(&FileLocation::PathBuf(
+            root.join("examples/toy/pasta/toy.r1cs"),
+        ))
+        .unwrap();
+        let pp = create_public_params::<G1, G2>(r1cs.clone()).unwrap();
+
+        let mut other_r1cs = r1cs.clone();
+        other_r1cs.io.step_in.swap(0, 1);
+        assert_ne!(r1cs.digest(), other_r1cs.digest());
+
+        let dir = tempfile::tempdir().unwrap();
+        for compression in [Compression::None, Compression::Gzip] {
+            let path = dir.path().join("toy.params");
+            save_public_params(&pp, &r1cs, &path, compression).unwrap();
+            // only the params file is left behind, no temporary file
+            assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+            let loaded = load_public_params::<G1, G2>(&path, &r1cs).unwrap();
+            assert_eq!(loaded.digest(), pp.digest());
+
+            assert!(matches!(
+                load_public_params::<G1, G2>(&path, &other_r1cs),
+                Err(NovaScotiaError::DigestMismatch(_))
+            ));
+        }
+
+        // parameters saved for an R1CS they were not created for
+        let mut narrow_r1cs = r1cs.clone();
+        narrow_r1cs.io.step_in.truncate(1);
+        narrow_r1cs.io.step_out.truncate(1);
+        assert!(matches!(
+            write_public_params(Vec::new(), &pp, &narrow_r1cs, Compression::None),
+            Err(NovaScotiaError::DigestMismatch(_))
+        ));
+
+        // parameters that do not match the digest in the header
+        let mut bytes = Vec::new();
+        write_public_params(&mut bytes, &pp, &r1cs, Compression::None).unwrap();
+        let header = read_params_header::<G1, G2, _>(&mut &bytes[..]).unwrap();
+        assert_eq!(header.compression, Compression::None);
+        assert_eq!(header.r1cs_digest, r1cs.digest());
+        assert_eq!(header.pp_digest, pp.digest());
+        bytes[4 + 4 + 1 + 32] ^= 1;
+        assert!(matches!(
+            read_public_params::<G1, G2, _>(&bytes[..], &r1cs),
+            Err(NovaScotiaError::DigestMismatch(_))
+        ));
+    }
+}
@@ -0,0 +1,78 @@
+// Verification of compressed proofs from a verifier key alone.
+//
+// Nothing here needs the R1CS, the public parameters or a witness generator, so a verification
+// service can depend on Nova Scotia with `default-features = false, features = ["verifier"]`.
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use ff::PrimeField;
+use nova_snark::traits::Engine;
+
+use crate::bundle::ProofBundle;
+use crate::compression::{read_verifier_key, CompressionBackend, CompressionVerifierKey};
+use crate::error::NovaScotiaError;
+use crate::F;
+
+/// The statement a proof bundle was verified for: `num_steps` applications of the step circuit
+/// take `z0` to `zn`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Verified<Fr: PrimeField> {
+    pub num_steps: usize,
+    pub z0: Vec<Fr>,
+    pub zn: Vec<Fr>,
+}
+
+/// Checks proof bundles compressed with backend `B` against one verifier key.
+pub struct Verifier<G1, G2, B>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+    B: CompressionBackend<G1, G2>,
+{
+    vk: CompressionVerifierKey<G1, G2, B>,
+}
+
+impl<G1, G2, B> Verifier<G1, G2, B>
+where
+    G1: Engine<Base = <G2 as Engine>::Scalar>,
+    G2: Engine<Base = <G1 as Engine>::Scalar>,
+    B: CompressionBackend<G1, G2>,
+{
+    pub fn new(vk: CompressionVerifierKey<G1, G2, B>) -> Self {
+        Verifier { vk }
+    }
+
+    /// load a verifier key written by [`crate::compression::save_verifier_key`]
+    pub fn load(path: &Path) -> Result<Self, NovaScotiaError> {
+        Self::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, NovaScotiaError> {
+        Ok(Self::new(read_verifier_key::<G1, G2, B, _>(reader)?))
+    }
+
+    /// verify a compressed proof bundle, returning the number of steps, `z_0` and `z_n` it was
+    /// checked for
+    ///
+    /// The bundle's header supplies `z_0`, so compare it with the input the proof should start
+    /// from before trusting `z_n`.
+    pub fn verify(&self, proof: &ProofBundle<G1, G2>) -> Result<Verified<F<G1>>, NovaScotiaError> {
+        let zn = proof.verify_compressed::<B>(&self.vk)?;
+        Ok(Verified {
+            num_steps: proof.header.num_steps,
+            z0: proof.header.z0.clone(),
+            zn,
+        })
+    }
+
+    /// verify the compressed proof bundle at `path`
+    pub fn verify_file(&self, path: &Path) -> Result<Verified<F<G1>>, NovaScotiaError> {
+        self.verify(&ProofBundle::load(path)?)
+    }
+
+    /// verify a compressed proof bundle read from `reader`
+    pub fn verify_reader<R: Read>(&self, reader: R) -> Result<Verified<F<G1>>, NovaScotiaError> {
+        self.verify(&ProofBundle::read(reader)?)
+    }
+}